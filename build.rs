@@ -0,0 +1,21 @@
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    // Generate the C header for the `capi` feature; see `include/psfparser.h`.
+    #[cfg(feature = "capi")]
+    {
+        use std::path::PathBuf;
+
+        println!("cargo:rerun-if-changed=cbindgen.toml");
+        println!("cargo:rerun-if-changed=src/capi/mod.rs");
+        let root = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap());
+        let out = PathBuf::from(std::env::var("OUT_DIR").unwrap());
+        let config = cbindgen::Config::from_file(root.join("cbindgen.toml")).unwrap();
+        cbindgen::Builder::new()
+            .with_config(config)
+            .with_src(root.join("src/capi/mod.rs"))
+            .generate()
+            .expect("failed to generate C header")
+            .write_to_file(out.join("psfparser.h"));
+    }
+}
@@ -1,4 +1,4 @@
-from psf_utils import PSF
+from psfparser import PSF
 import matplotlib.pyplot as plt
 
 psf = PSF('timeSweep.tran.tran')
@@ -0,0 +1,144 @@
+#ifndef PSFPARSER_H
+#define PSFPARSER_H
+
+/* Generated by cbindgen from src/capi/mod.rs. Do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * An open PSF file containing a transient, AC or DC analysis.
+ */
+typedef struct PsfHandle PsfHandle;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Gets the message describing the most recent error on the calling thread,
+ * or `NULL` if no error has occurred.
+ *
+ * The message remains valid until the next error on the same thread.
+ */
+const char *psf_last_error(void);
+
+/**
+ * Opens and parses the PSF file at `path`, which must contain a transient, AC or DC analysis.
+ *
+ * Returns `NULL` on failure. The handle must be freed with [`psf_close`].
+ *
+ * # Safety
+ *
+ * `path` must be a valid NUL-terminated string.
+ */
+struct PsfHandle *psf_open(const char *path);
+
+/**
+ * Frees a handle returned by [`psf_open`]. Does nothing if `handle` is `NULL`.
+ *
+ * # Safety
+ *
+ * `handle` must be null or a pointer returned by [`psf_open`] that has not been closed.
+ */
+void psf_close(struct PsfHandle *handle);
+
+/**
+ * Gets the number of signals in the file, including the sweep variable.
+ *
+ * # Safety
+ *
+ * `handle` must be a pointer returned by [`psf_open`] that has not been closed.
+ */
+size_t psf_signal_count(const struct PsfHandle *handle);
+
+/**
+ * Gets the name of the signal at `index`, or `NULL` if `index` is out of range.
+ *
+ * The sweep variable, if any, comes first, followed by the other signals sorted by name.
+ * The name remains valid until the handle is closed.
+ *
+ * # Safety
+ *
+ * `handle` must be a pointer returned by [`psf_open`] that has not been closed.
+ */
+const char *psf_signal_name(const struct PsfHandle *handle, size_t index);
+
+/**
+ * Gets the number of points in the signal named `name`, or -1 if there is no such signal.
+ *
+ * # Safety
+ *
+ * `handle` must be a pointer returned by [`psf_open`] that has not been closed,
+ * and `name` must be a valid NUL-terminated string.
+ */
+ptrdiff_t psf_signal_len(const struct PsfHandle *handle, const char *name);
+
+/**
+ * Returns 1 if the signal named `name` is complex, 0 if it is real,
+ * or -1 if there is no such signal.
+ *
+ * # Safety
+ *
+ * `handle` must be a pointer returned by [`psf_open`] that has not been closed,
+ * and `name` must be a valid NUL-terminated string.
+ */
+int psf_signal_is_complex(const struct PsfHandle *handle, const char *name);
+
+/**
+ * Copies up to `len` points of the real signal named `name` into `buf`.
+ *
+ * Returns the number of points in the signal, which may exceed `len`,
+ * or -1 if there is no such signal or it is complex.
+ *
+ * # Safety
+ *
+ * `handle` must be a pointer returned by [`psf_open`] that has not been closed,
+ * `name` must be a valid NUL-terminated string,
+ * and `buf` must be valid for writing `len` values.
+ */
+ptrdiff_t psf_copy_signal(const struct PsfHandle *handle,
+                          const char *name,
+                          double *buf,
+                          size_t len);
+
+/**
+ * Copies up to `len` points of the signal named `name` into `re` and `im`.
+ *
+ * The imaginary parts of a real signal are zero.
+ * Returns the number of points in the signal, which may exceed `len`,
+ * or -1 if there is no such signal.
+ *
+ * # Safety
+ *
+ * `handle` must be a pointer returned by [`psf_open`] that has not been closed,
+ * `name` must be a valid NUL-terminated string,
+ * and `re` and `im` must each be valid for writing `len` values.
+ */
+ptrdiff_t psf_copy_complex_signal(const struct PsfHandle *handle,
+                                  const char *name,
+                                  double *re,
+                                  double *im,
+                                  size_t len);
+
+/**
+ * Gets the header value named `name`, such as `"simulator"`, formatted as a string.
+ *
+ * Returns `NULL` if there is no such header value.
+ * The value remains valid until the handle is closed.
+ *
+ * # Safety
+ *
+ * `handle` must be a pointer returned by [`psf_open`] that has not been closed,
+ * and `name` must be a valid NUL-terminated string.
+ */
+const char *psf_header_value(const struct PsfHandle *handle, const char *name);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* PSFPARSER_H */
@@ -1,91 +1,175 @@
 use num::complex::Complex64;
 use std::collections::HashMap;
+use std::f64::consts::PI;
 
-use crate::ascii::ast::{PsfAst, Trace, Values};
+use crate::ascii::ast::{PsfAst, SignalValues, Values};
 use crate::bin_search_before;
+use crate::raw::{Plot, RawValues};
+use crate::Result;
+use anyhow::bail;
 
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AcData {
     pub signals: HashMap<String, Vec<Complex64>>,
     pub freq: Vec<f64>,
+    /// The units of each signal, where known.
+    pub units: HashMap<String, String>,
+}
+
+/// A single-ended or differential quantity in an AC analysis.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Probe<'a> {
+    /// A single signal.
+    Single(&'a str),
+    /// The difference between a positive and a negative signal.
+    Diff(&'a str, &'a str),
+}
+
+impl<'a> From<&'a str> for Probe<'a> {
+    fn from(value: &'a str) -> Self {
+        Self::Single(value)
+    }
+}
+
+impl<'a> From<(&'a str, &'a str)> for Probe<'a> {
+    fn from(value: (&'a str, &'a str)) -> Self {
+        Self::Diff(value.0, value.1)
+    }
 }
 
 impl AcData {
+    /// Reads AC data from an ASCII PSF.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the file is not a valid AC analysis; see [`Self::try_from_ascii`].
     pub fn from_ascii(ast: &PsfAst) -> Self {
-        // Assume all groups have count = 1
-        // group name -> signal name
-        let mut groups = HashMap::<&str, &str>::new();
-        let mut i = 0;
-        while i < ast.traces.len() {
-            match ast.traces[i] {
-                Trace::Group { name: group, count } => {
-                    debug_assert!(count >= 0);
-                    let count = count as usize;
-                    for j in 1..=count {
-                        if let Trace::Signal { name, .. } = ast.traces[i + j] {
-                            groups.insert(group, name);
-                        } else {
-                            panic!("Expected signal; found group");
-                        }
-                    }
-                    i += count + 1;
-                }
-                Trace::Signal { name, .. } => {
-                    groups.insert(name, name);
-                    i += 1;
-                }
-            }
-        }
+        Self::try_from_ascii(ast).unwrap()
+    }
+
+    /// Reads AC data from an ASCII PSF.
+    ///
+    /// Fails if the frequencies are not real or any signal is not complex.
+    pub fn try_from_ascii(ast: &PsfAst) -> Result<Self> {
+        Self::from_ascii_sweep(ast, "freq")
+    }
+
+    /// Reads complex signals swept over the variable `sweep`,
+    /// whose values are stored in `freq`.
+    pub(crate) fn from_ascii_sweep(ast: &PsfAst, sweep: &str) -> Result<Self> {
+        let groups = ast.groups();
+
+        let mut data = Self::from_ascii_values(&groups, sweep, ast.values.iter())?;
+        data.units = ast.signal_units();
+        Ok(data)
+    }
 
+    /// Collects sweep values, given a map from group name to signal name.
+    ///
+    /// The values of the sweep variable `sweep` are stored in `freq`.
+    /// Units are left empty.
+    pub(crate) fn from_ascii_values<'a>(
+        groups: &HashMap<&str, &str>,
+        sweep: &str,
+        values: impl Iterator<Item = &'a SignalValues<'a>>,
+    ) -> Result<Self> {
         let mut signals = HashMap::<String, Vec<Complex64>>::new();
         let mut freq = Vec::<f64>::new();
-        for v in ast.values.iter() {
-            if v.signal == "freq" {
-                if let Values::Real(values) = &v.values {
-                    debug_assert_eq!(values.len(), 1);
-                    freq.push(values[0]);
-                } else {
-                    panic!("Expected real signal values; found complex");
-                }
+        for v in values {
+            if v.signal == sweep {
+                let Values::Real(values) = &v.values else {
+                    bail!("expected real values for sweep `{sweep}`");
+                };
+                debug_assert_eq!(values.len(), 1);
+                freq.push(values[0]);
             } else if let Values::Complex(values) = &v.values {
                 debug_assert_eq!(values.len(), 1);
-                if let Some(lst) = signals.get_mut(groups[v.signal]) {
-                    lst.push(values[0]);
-                } else {
-                    signals.insert(groups[v.signal].to_string(), vec![values[0]]);
-                }
+                let Some(name) = groups.get(v.signal) else {
+                    bail!("values for undeclared signal `{}`", v.signal);
+                };
+                signals.entry(name.to_string()).or_default().push(values[0]);
             } else {
-                panic!("Expected complex signal values; found real");
+                bail!("expected complex values for signal `{}`", v.signal);
+            }
+        }
+
+        Ok(Self {
+            signals,
+            freq,
+            units: HashMap::new(),
+        })
+    }
+
+    /// Reads a complex-valued SPICE raw file plot whose first variable is frequency.
+    pub fn from_raw(plot: Plot) -> Result<Self> {
+        let RawValues::Complex(mut values) = plot.values else {
+            bail!("expected complex raw file values; found real");
+        };
+        if values.is_empty() {
+            bail!("raw file plot has no variables");
+        }
+        let freq = values.remove(0).iter().map(|f| f.re).collect();
+        let mut units = HashMap::from([("freq".to_string(), "Hz".to_string())]);
+        let mut signals = HashMap::new();
+        for (var, values) in plot.variables.into_iter().skip(1).zip(values) {
+            if let Some(u) = var.units() {
+                units.insert(var.name.clone(), u.to_string());
             }
+            signals.insert(var.name, values);
         }
 
-        Self { signals, freq }
+        Ok(Self {
+            signals,
+            freq,
+            units,
+        })
     }
 
-    pub fn from_binary(mut ast: crate::binary::ast::PsfAst) -> Self {
+    /// Reads AC data from a binary PSF.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the file is not a valid AC analysis; see [`Self::try_from_binary`].
+    pub fn from_binary(ast: crate::binary::ast::PsfAst) -> Self {
+        Self::try_from_binary(ast).unwrap()
+    }
+
+    /// Reads AC data from a binary PSF.
+    ///
+    /// Fails if the file is not swept over frequency,
+    /// or any signal is missing or not complex.
+    pub fn try_from_binary(ast: crate::binary::ast::PsfAst) -> Result<Self> {
+        match ast.sweeps.first() {
+            Some(sweep) if sweep.name == "freq" => Self::from_binary_sweep(ast),
+            Some(sweep) => bail!(
+                "ac analysis expects to sweep frequency; found `{}`",
+                sweep.name
+            ),
+            None => bail!("ac analysis has no sweep"),
+        }
+    }
+
+    /// Reads complex signals swept over the first sweep variable,
+    /// whose values are stored in `freq`.
+    pub(crate) fn from_binary_sweep(mut ast: crate::binary::ast::PsfAst) -> Result<Self> {
         let mut signals = HashMap::<String, Vec<Complex64>>::new();
         for group in ast.traces.iter() {
             for sig in group.signals() {
-                let v = ast
-                    .values
-                    .values
-                    .remove(&sig.id)
-                    .expect("missing values for trace");
-                signals.insert(sig.name.to_string(), v.unwrap_complex());
+                signals.insert(sig.name.to_string(), ast.values.take_complex(sig)?);
             }
         }
 
-        assert_eq!(
-            ast.sweeps[0].name, "freq",
-            "ac analysis expects to sweep frequency"
-        );
-        let freq = ast
-            .values
-            .values
-            .remove(&ast.sweeps[0].id)
-            .unwrap()
-            .unwrap_real();
+        let Some(sweep) = ast.sweeps.first() else {
+            bail!("missing sweep");
+        };
+        let freq = ast.values.take_real(sweep)?;
 
-        Self { signals, freq }
+        Ok(Self {
+            signals,
+            freq,
+            units: ast.signal_units(),
+        })
     }
 
     /// Gets the index into the data arrays
@@ -98,4 +182,260 @@ impl AcData {
     pub fn signal(&self, name: &str) -> Option<&Vec<Complex64>> {
         self.signals.get(name)
     }
+
+    /// Gets the values of a single-ended or differential quantity.
+    pub fn probe<'a>(&self, probe: impl Into<Probe<'a>>) -> Option<Vec<Complex64>> {
+        match probe.into() {
+            Probe::Single(name) => self.signal(name).cloned(),
+            Probe::Diff(p, n) => {
+                let (p, n) = (self.signal(p)?, self.signal(n)?);
+                Some(p.iter().zip(n.iter()).map(|(p, n)| p - n).collect())
+            }
+        }
+    }
+
+    /// Computes the transfer function from `input` to `output` at each frequency.
+    pub fn ratio<'a, 'b>(
+        &self,
+        output: impl Into<Probe<'a>>,
+        input: impl Into<Probe<'b>>,
+    ) -> Option<Vec<Complex64>> {
+        let output = self.probe(output)?;
+        let input = self.probe(input)?;
+        Some(
+            output
+                .iter()
+                .zip(input.iter())
+                .map(|(o, i)| o / i)
+                .collect(),
+        )
+    }
+
+    /// The magnitude of a quantity at each frequency.
+    pub fn mag<'a>(&self, probe: impl Into<Probe<'a>>) -> Option<Vec<f64>> {
+        Some(mag(&self.probe(probe)?))
+    }
+
+    /// The magnitude of a voltage or current quantity at each frequency, in dB.
+    pub fn db20<'a>(&self, probe: impl Into<Probe<'a>>) -> Option<Vec<f64>> {
+        Some(db20(&self.probe(probe)?))
+    }
+
+    /// The magnitude of a power quantity at each frequency, in dB.
+    pub fn db10<'a>(&self, probe: impl Into<Probe<'a>>) -> Option<Vec<f64>> {
+        Some(db10(&self.probe(probe)?))
+    }
+
+    /// The phase of a quantity at each frequency, in degrees within `(-180, 180]`.
+    pub fn phase_deg<'a>(&self, probe: impl Into<Probe<'a>>) -> Option<Vec<f64>> {
+        Some(phase_deg(&self.probe(probe)?))
+    }
+
+    /// The phase of a quantity at each frequency, in degrees,
+    /// with discontinuities of 360 degrees removed.
+    pub fn unwrapped_phase_deg<'a>(&self, probe: impl Into<Probe<'a>>) -> Option<Vec<f64>> {
+        Some(unwrap_deg(&phase_deg(&self.probe(probe)?)))
+    }
+
+    /// The group delay of a quantity at each frequency, in seconds.
+    pub fn group_delay<'a>(&self, probe: impl Into<Probe<'a>>) -> Option<Vec<f64>> {
+        Some(group_delay(&self.freq, &self.probe(probe)?))
+    }
+
+    /// The -3 dB bandwidth of the transfer function from `reference` to `signal`.
+    ///
+    /// See [`f_3db`].
+    pub fn f_3db<'a, 'b>(
+        &self,
+        signal: impl Into<Probe<'a>>,
+        reference: impl Into<Probe<'b>>,
+    ) -> Option<f64> {
+        f_3db(&self.freq, &self.ratio(signal, reference)?)
+    }
+
+    /// The unity gain frequency of a quantity.
+    ///
+    /// See [`unity_gain_freq`].
+    pub fn unity_gain_freq<'a>(&self, probe: impl Into<Probe<'a>>) -> Option<f64> {
+        unity_gain_freq(&self.freq, &self.probe(probe)?)
+    }
+
+    /// The phase margin of a loop gain quantity, in degrees.
+    ///
+    /// See [`phase_margin`].
+    pub fn phase_margin<'a>(&self, probe: impl Into<Probe<'a>>) -> Option<f64> {
+        phase_margin(&self.freq, &self.probe(probe)?)
+    }
+
+    /// The gain margin of a loop gain quantity, in dB.
+    ///
+    /// See [`gain_margin`].
+    pub fn gain_margin<'a>(&self, probe: impl Into<Probe<'a>>) -> Option<f64> {
+        gain_margin(&self.freq, &self.probe(probe)?)
+    }
+
+    /// The maximum gain of a quantity, in dB.
+    ///
+    /// See [`peak`].
+    pub fn peak_gain<'a>(&self, probe: impl Into<Probe<'a>>) -> Option<f64> {
+        Some(peak(&self.freq, &self.probe(probe)?)?.1)
+    }
+
+    /// The frequency at which a quantity reaches its maximum gain.
+    ///
+    /// See [`peak`].
+    pub fn resonant_freq<'a>(&self, probe: impl Into<Probe<'a>>) -> Option<f64> {
+        Some(peak(&self.freq, &self.probe(probe)?)?.0)
+    }
+}
+
+/// Computes the magnitude of each value.
+pub fn mag(values: &[Complex64]) -> Vec<f64> {
+    values.iter().map(|x| x.norm()).collect()
+}
+
+/// Computes `20 log10(|x|)` for each value `x`.
+pub fn db20(values: &[Complex64]) -> Vec<f64> {
+    values.iter().map(|x| 20. * x.norm().log10()).collect()
+}
+
+/// Computes `10 log10(|x|)` for each value `x`.
+pub fn db10(values: &[Complex64]) -> Vec<f64> {
+    values.iter().map(|x| 10. * x.norm().log10()).collect()
+}
+
+/// Computes the phase of each value, in degrees within `(-180, 180]`.
+pub fn phase_deg(values: &[Complex64]) -> Vec<f64> {
+    values.iter().map(|x| x.arg().to_degrees()).collect()
+}
+
+/// Removes jumps of more than 180 degrees between consecutive phases.
+pub fn unwrap_deg(phase: &[f64]) -> Vec<f64> {
+    let mut out: Vec<f64> = Vec::with_capacity(phase.len());
+    let mut offset = 0f64;
+    for (i, &p) in phase.iter().enumerate() {
+        if i > 0 {
+            let delta = p + offset - out[i - 1];
+            offset -= 360. * (delta / 360.).round();
+        }
+        out.push(p + offset);
+    }
+    out
+}
+
+/// Computes the group delay, `-d(phase)/d(omega)`, of a response sampled at `freq`.
+///
+/// Derivatives are estimated using central differences in the interior
+/// and one-sided differences at the endpoints.
+pub fn group_delay(freq: &[f64], values: &[Complex64]) -> Vec<f64> {
+    let n = freq.len().min(values.len());
+    if n < 2 {
+        return vec![0.; n];
+    }
+    let phase = unwrap_deg(&phase_deg(&values[..n]));
+    (0..n)
+        .map(|i| {
+            let (lo, hi) = (i.saturating_sub(1), (i + 1).min(n - 1));
+            let dphi = (phase[hi] - phase[lo]).to_radians();
+            let domega = 2. * PI * (freq[hi] - freq[lo]);
+            -dphi / domega
+        })
+        .collect()
+}
+
+/// The index of the first positive frequency.
+///
+/// Points before it, such as a DC point at 0 Hz, have no position on a
+/// log-frequency axis and are skipped when interpolating in log-frequency.
+fn first_positive(freq: &[f64]) -> usize {
+    freq.partition_point(|&f| f <= 0.)
+}
+
+/// Finds the first frequency at which `y` crosses `level`,
+/// interpolating linearly in log-frequency.
+fn crossing(freq: &[f64], y: &[f64], level: f64) -> Option<f64> {
+    let start = first_positive(freq);
+    let i = start
+        + y.get(start..)?
+            .windows(2)
+            .position(|w| (w[0] - level) * (w[1] - level) <= 0. && w[0] != w[1])?;
+    let (f0, f1) = (freq[i].log10(), freq[i + 1].log10());
+    let frac = (level - y[i]) / (y[i + 1] - y[i]);
+    Some(10f64.powf(f0 + frac * (f1 - f0)))
+}
+
+/// Evaluates `y` at frequency `f`, interpolating linearly in log-frequency.
+///
+/// There must be at least two positive frequencies.
+fn interp_log(freq: &[f64], y: &[f64], f: f64) -> f64 {
+    let start = first_positive(freq);
+    let (freq, y) = (&freq[start..], &y[start..]);
+    let i = freq.partition_point(|&x| x <= f).clamp(1, freq.len() - 1);
+    let (f0, f1) = (freq[i - 1].log10(), freq[i].log10());
+    let frac = (f.log10() - f0) / (f1 - f0);
+    y[i - 1] + frac * (y[i] - y[i - 1])
+}
+
+/// Finds the first frequency at which the gain of a transfer function
+/// falls 3 dB below its gain at the lowest simulated frequency.
+pub fn f_3db(freq: &[f64], values: &[Complex64]) -> Option<f64> {
+    let db = db20(values);
+    let level = db.first()? - 10. * 2f64.log10();
+    crossing(freq, &db, level)
+}
+
+/// Finds the first frequency at which the gain of a transfer function crosses 0 dB.
+pub fn unity_gain_freq(freq: &[f64], values: &[Complex64]) -> Option<f64> {
+    crossing(freq, &db20(values), 0.)
+}
+
+/// Computes the phase margin of a loop gain `T`, in degrees.
+///
+/// The loop gain is defined such that the closed loop response is `T / (1 + T)`,
+/// so the phase margin is `180 + phase(T)` at the unity gain frequency.
+/// The phase is unwrapped starting from the lowest simulated frequency.
+pub fn phase_margin(freq: &[f64], values: &[Complex64]) -> Option<f64> {
+    let fu = unity_gain_freq(freq, values)?;
+    let phase = unwrap_deg(&phase_deg(values));
+    Some(180. + interp_log(freq, &phase, fu))
+}
+
+/// Finds the first frequency at which the unwrapped phase of a loop gain `T`
+/// reaches -180 degrees.
+pub fn phase_crossover_freq(freq: &[f64], values: &[Complex64]) -> Option<f64> {
+    crossing(freq, &unwrap_deg(&phase_deg(values)), -180.)
+}
+
+/// Computes the gain margin of a loop gain `T`, in dB.
+///
+/// The gain margin is `-20 log10(|T|)` at the [phase crossover frequency](phase_crossover_freq).
+pub fn gain_margin(freq: &[f64], values: &[Complex64]) -> Option<f64> {
+    let f180 = phase_crossover_freq(freq, values)?;
+    Some(-interp_log(freq, &db20(values), f180))
+}
+
+/// Finds the peak gain of a response,
+/// returning the frequency of the peak and the gain in dB.
+///
+/// Peaks between simulated frequencies are estimated by fitting
+/// a parabola in log-frequency to the three points around the largest sample.
+pub fn peak(freq: &[f64], values: &[Complex64]) -> Option<(f64, f64)> {
+    let db = db20(values);
+    let i = db.iter().enumerate().max_by(|a, b| a.1.total_cmp(b.1))?.0;
+    if i <= first_positive(freq) || i == db.len() - 1 {
+        return Some((freq[i], db[i]));
+    }
+
+    let x = [freq[i - 1].log10(), freq[i].log10(), freq[i + 1].log10()];
+    let y = [db[i - 1], db[i], db[i + 1]];
+    let d01 = (y[1] - y[0]) / (x[1] - x[0]);
+    let d12 = (y[2] - y[1]) / (x[2] - x[1]);
+    let a = (d12 - d01) / (x[2] - x[0]);
+    if a >= 0. {
+        return Some((freq[i], db[i]));
+    }
+    let b = d01 - a * (x[0] + x[1]);
+    let xp = (-b / (2. * a)).clamp(x[0], x[2]);
+    let yp = y[1] + d01 * (xp - x[1]) + a * (xp - x[0]) * (xp - x[1]);
+    Some((10f64.powf(xp), yp))
 }
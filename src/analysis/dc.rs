@@ -1,47 +1,50 @@
 use std::collections::HashMap;
 
-use crate::ascii::ast::{PsfAst, Trace, Values};
+use anyhow::bail;
 
+use crate::ascii::ast::{PsfAst, Values};
+use crate::raw::{Plot, RawValues};
+use crate::Result;
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DcData {
     Op(OpData),
     Sweep(SweepData),
 }
 
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct OpData {
     pub signals: HashMap<String, f64>,
+    /// The units of each signal, where known.
+    pub units: HashMap<String, String>,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SweepData {
     pub signals: HashMap<String, Vec<f64>>,
     pub param: (String, Vec<f64>),
+    /// The units of each signal and of the swept parameter, where known.
+    pub units: HashMap<String, String>,
 }
 
 impl DcData {
+    /// Reads DC data from an ASCII PSF.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the file is not a valid DC analysis; see [`Self::try_from_ast`].
     pub fn from_ast(ast: &PsfAst) -> Self {
-        // Assume all groups have count = 1
-        // group name -> signal name
-        let mut groups = HashMap::<&str, &str>::new();
-        let mut i = 0;
-        while i < ast.traces.len() {
-            match ast.traces[i] {
-                Trace::Group { name: group, count } => {
-                    debug_assert!(count >= 0);
-                    let count = count as usize;
-                    for j in 1..=count {
-                        if let Trace::Signal { name, .. } = ast.traces[i + j] {
-                            groups.insert(group, name);
-                        } else {
-                            panic!("Expected signal; found group");
-                        }
-                    }
-                    i += count + 1;
-                }
-                Trace::Signal { name, .. } => {
-                    groups.insert(name, name);
-                    i += 1;
-                }
-            }
-        }
+        Self::try_from_ast(ast).unwrap()
+    }
+
+    /// Reads DC data from an ASCII PSF.
+    ///
+    /// Fails if any signal is not real.
+    pub fn try_from_ast(ast: &PsfAst) -> Result<Self> {
+        let groups = ast.groups();
 
         let sweepvar = if ast.sweeps.is_empty() {
             None
@@ -52,31 +55,72 @@ impl DcData {
         let mut signals = HashMap::<String, Vec<f64>>::new();
         let mut sweepvals = Vec::<f64>::new();
         for v in ast.values.iter() {
-            if let Values::Real(values) = &v.values {
-                debug_assert_eq!(values.len(), 1);
-                if Some(v.signal) == sweepvar.as_deref() {
-                    sweepvals.push(values[0]);
-                } else {
-                    let group = groups.get(v.signal).unwrap_or(&v.signal);
-                    if let Some(lst) = signals.get_mut(*group) {
-                        lst.push(values[0]);
-                    } else {
-                        signals.insert(group.to_string(), vec![values[0]]);
-                    }
-                }
+            let Values::Real(values) = &v.values else {
+                bail!("expected real values for signal `{}`", v.signal);
+            };
+            debug_assert_eq!(values.len(), 1);
+            if Some(v.signal) == sweepvar.as_deref() {
+                sweepvals.push(values[0]);
             } else {
-                panic!("Expected real signal values; found complex");
+                let group = groups.get(v.signal).unwrap_or(&v.signal);
+                signals
+                    .entry(group.to_string())
+                    .or_default()
+                    .push(values[0]);
             }
         }
 
-        match sweepvar {
+        Ok(match sweepvar {
             Some(name) => Self::Sweep(SweepData {
                 signals,
                 param: (name, sweepvals),
+                units: ast.signal_units(),
             }),
             None => Self::Op(OpData {
                 signals: HashMap::from_iter(signals.into_iter().map(|(k, v)| (k, v[0]))),
+                units: ast.signal_units(),
             }),
+        })
+    }
+
+    /// Reads a real-valued SPICE raw file plot.
+    ///
+    /// Operating point plots become [`DcData::Op`]. Any other plot is read as a sweep
+    /// of its first variable.
+    pub fn from_raw(plot: Plot) -> Result<Self> {
+        let RawValues::Real(values) = plot.values else {
+            bail!("expected real raw file values; found complex");
+        };
+        let units = plot
+            .variables
+            .iter()
+            .filter_map(|var| Some((var.name.clone(), var.units()?.to_string())))
+            .collect();
+        let mut signals = plot
+            .variables
+            .into_iter()
+            .map(|var| var.name)
+            .zip(values)
+            .collect::<Vec<_>>();
+
+        if plot.name.to_ascii_lowercase().contains("operating point") {
+            Ok(Self::Op(OpData {
+                signals: signals
+                    .into_iter()
+                    .filter_map(|(name, values)| Some((name, *values.first()?)))
+                    .collect(),
+                units,
+            }))
+        } else {
+            if signals.is_empty() {
+                bail!("raw file plot has no variables");
+            }
+            let param = signals.remove(0);
+            Ok(Self::Sweep(SweepData {
+                signals: signals.into_iter().collect(),
+                param,
+                units,
+            }))
         }
     }
 }
@@ -0,0 +1,275 @@
+//! Conversion of analog waveforms to logic levels.
+
+use anyhow::bail;
+
+use crate::Result;
+
+/// A logic level.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Logic {
+    Low,
+    High,
+    /// An unknown level, between the input thresholds.
+    X,
+}
+
+impl Logic {
+    /// The character representing this level in a VCD file.
+    pub fn to_char(self) -> char {
+        match self {
+            Self::Low => '0',
+            Self::High => '1',
+            Self::X => 'x',
+        }
+    }
+}
+
+/// Input thresholds for converting analog voltages to logic levels.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Threshold {
+    /// Voltages at or below this are logic low.
+    pub vil: f64,
+    /// Voltages at or above this are logic high.
+    pub vih: f64,
+    /// If `true`, voltages between the thresholds keep the previous logic level.
+    /// Otherwise, they are [`Logic::X`].
+    ///
+    /// A waveform that starts between the thresholds is [`Logic::X`] either way.
+    pub hysteresis: bool,
+}
+
+/// The region of the input range a voltage lies in.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
+enum Region {
+    Low,
+    Mid,
+    High,
+}
+
+impl Threshold {
+    /// Creates thresholds without hysteresis.
+    ///
+    /// Fails if `vil` is greater than `vih` or either is NaN.
+    pub fn new(vil: f64, vih: f64) -> Result<Self> {
+        if vil.is_nan() || vih.is_nan() {
+            bail!("input thresholds must not be NaN");
+        }
+        if vil > vih {
+            bail!("low input threshold {vil} is greater than high input threshold {vih}");
+        }
+        Ok(Self {
+            vil,
+            vih,
+            hysteresis: false,
+        })
+    }
+
+    /// Creates thresholds at 30% and 70% of the supply voltage `vdd`.
+    ///
+    /// Fails if `vdd` is negative or NaN.
+    pub fn cmos(vdd: f64) -> Result<Self> {
+        Self::new(0.3 * vdd, 0.7 * vdd)
+    }
+
+    /// Enables hysteresis.
+    pub fn with_hysteresis(mut self) -> Self {
+        self.hysteresis = true;
+        self
+    }
+
+    fn region(&self, v: f64) -> Region {
+        if v >= self.vih {
+            Region::High
+        } else if v <= self.vil {
+            Region::Low
+        } else {
+            Region::Mid
+        }
+    }
+
+    fn level(&self, region: Region, prev: Logic) -> Logic {
+        match region {
+            Region::Low => Logic::Low,
+            Region::High => Logic::High,
+            Region::Mid if self.hysteresis => prev,
+            Region::Mid => Logic::X,
+        }
+    }
+
+    /// Converts a waveform to a sequence of logic level changes.
+    ///
+    /// Returns the time of each change and the new level, starting with the level
+    /// at the first time point. The waveform is treated as piecewise linear, so
+    /// changes occur at the interpolated times at which thresholds are crossed.
+    pub fn digitize(&self, time: &[f64], values: &[f64]) -> Vec<(f64, Logic)> {
+        let (Some(&t0), Some(&v0)) = (time.first(), values.first()) else {
+            return Vec::new();
+        };
+        let mut region = self.region(v0);
+        let mut state = self.level(region, Logic::X);
+        let mut events = vec![(t0, state)];
+        let has_mid = self.vil < self.vih;
+
+        for i in 1..time.len().min(values.len()) {
+            let next = self.region(values[i]);
+            // Visit each region between the previous and next one in turn.
+            while region != next {
+                let rising = next > region;
+                region = match region {
+                    Region::Low | Region::High if has_mid => Region::Mid,
+                    _ if rising => Region::High,
+                    _ => Region::Low,
+                };
+                let boundary = match region {
+                    Region::High => self.vih,
+                    Region::Low => self.vil,
+                    Region::Mid if rising => self.vil,
+                    Region::Mid => self.vih,
+                };
+                let level = self.level(region, state);
+                if level != state {
+                    let (ta, tb) = (time[i - 1], time[i]);
+                    let (va, vb) = (values[i - 1], values[i]);
+                    let t = ta + (tb - ta) * (boundary - va) / (vb - va);
+                    events.push((t, level));
+                    state = level;
+                }
+            }
+        }
+
+        events
+    }
+}
+
+/// Gets the level at time `t` from a sequence of level changes
+/// produced by [`Threshold::digitize`].
+///
+/// Returns `None` if `t` is before the first change.
+pub fn level_at(events: &[(f64, Logic)], t: f64) -> Option<Logic> {
+    let i = events.partition_point(|(te, _)| *te <= t);
+    Some(events.get(i.checked_sub(1)?)?.1)
+}
+
+/// The kind of clock edge to sample on.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Edge {
+    Rising,
+    Falling,
+    Both,
+}
+
+/// Gets the times of the edges in a sequence of level changes
+/// produced by [`Threshold::digitize`].
+///
+/// An edge occurs when a signal reaches a valid level opposite to its previous
+/// valid level; intermediate [`Logic::X`] levels are ignored.
+pub fn edges(events: &[(f64, Logic)], edge: Edge) -> Vec<f64> {
+    let mut prev = None;
+    let mut times = Vec::new();
+    for &(t, level) in events {
+        if level == Logic::X {
+            continue;
+        }
+        if matches!(
+            (prev, level, edge),
+            (Some(Logic::Low), Logic::High, Edge::Rising | Edge::Both)
+                | (Some(Logic::High), Logic::Low, Edge::Falling | Edge::Both)
+        ) {
+            times.push(t);
+        }
+        prev = Some(level);
+    }
+    times
+}
+
+/// The encoding of the integer codes on a bus.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Encoding {
+    Unsigned,
+    /// Two's complement.
+    Signed,
+    /// Reflected binary Gray code.
+    Gray,
+}
+
+impl Encoding {
+    /// Decodes the `width` least significant bits of `raw`.
+    pub fn decode(self, raw: u64, width: usize) -> i64 {
+        match self {
+            Self::Unsigned => raw as i64,
+            Self::Signed if width > 0 => {
+                let shift = 64 - width.min(64) as u32;
+                ((raw << shift) as i64) >> shift
+            }
+            Self::Signed => 0,
+            Self::Gray => {
+                let mut binary = raw;
+                let mut shift = raw >> 1;
+                while shift != 0 {
+                    binary ^= shift;
+                    shift >>= 1;
+                }
+                binary as i64
+            }
+        }
+    }
+}
+
+/// The bits of a bus, converted to logic levels.
+///
+/// Produced by [`TransientData::bus`](crate::analysis::transient::TransientData::bus).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bus {
+    /// The name of the signal for each bit, least significant first.
+    pub signals: Vec<String>,
+    /// The level changes of each bit, least significant first.
+    pub bits: Vec<Vec<(f64, Logic)>>,
+}
+
+impl Bus {
+    /// The number of bits in the bus.
+    #[inline]
+    pub fn width(&self) -> usize {
+        self.bits.len()
+    }
+
+    /// Gets the level of each bit at time `t`, least significant first.
+    pub fn levels(&self, t: f64) -> Vec<Option<Logic>> {
+        self.bits.iter().map(|events| level_at(events, t)).collect()
+    }
+
+    /// Gets the integer code on the bus at time `t`.
+    ///
+    /// Returns `None` if any bit is [`Logic::X`] or `t` is before the start of the waveforms.
+    pub fn code(&self, t: f64, encoding: Encoding) -> Option<i64> {
+        let mut raw = 0;
+        for (i, events) in self.bits.iter().enumerate() {
+            match level_at(events, t)? {
+                Logic::Low => {}
+                Logic::High => raw |= 1 << i,
+                Logic::X => return None,
+            }
+        }
+        Some(encoding.decode(raw, self.width()))
+    }
+
+    /// Gets the integer code on the bus at each of the given times.
+    pub fn codes(&self, times: &[f64], encoding: Encoding) -> Vec<Option<i64>> {
+        times.iter().map(|&t| self.code(t, encoding)).collect()
+    }
+}
+
+/// Removes a `v(...)` probe wrapper from a signal name.
+pub(crate) fn strip_probe(name: &str) -> &str {
+    name.strip_prefix("v(")
+        .and_then(|name| name.strip_suffix(')'))
+        .unwrap_or(name)
+}
+
+/// Splits a bus bit name such as `v(dout[3])` or `dout[3]` into
+/// the bus name (`dout`) and the bit index (`3`).
+pub(crate) fn bus_bit(name: &str) -> Option<(&str, usize)> {
+    let name = strip_probe(name);
+    let (bus, index) = name.strip_suffix(']')?.rsplit_once('[')?;
+    Some((bus, index.parse().ok()?))
+}
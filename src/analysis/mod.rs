@@ -1,3 +1,97 @@
+use anyhow::bail;
+
+use crate::ascii::ast::PsfAst as AsciiAst;
+use crate::binary::ast::PsfAst as BinaryAst;
+use crate::Result;
+
 pub mod ac;
 pub mod dc;
+pub mod digital;
+pub mod montecarlo;
+pub mod noise;
+pub mod pac;
+pub mod pss;
+pub mod sp;
+#[cfg(feature = "fft")]
+pub mod spectrum;
+pub mod stb;
 pub mod transient;
+pub mod xf;
+
+#[cfg(test)]
+mod tests;
+
+/// Analysis results that can be read from PSF files in either format.
+pub trait FromPsf: Sized {
+    fn from_ascii_ast(ast: &AsciiAst) -> Result<Self>;
+
+    fn from_binary_ast(ast: BinaryAst) -> Result<Self>;
+
+    /// Parses a PSF file, detecting whether it is in ASCII or binary format.
+    fn from_psf(data: &[u8]) -> Result<Self> {
+        if is_ascii(data) {
+            let ast = crate::ascii::parse(std::str::from_utf8(data)?)?;
+            Self::from_ascii_ast(&ast)
+        } else {
+            Self::from_binary_ast(crate::binary::parse(data)?)
+        }
+    }
+
+    /// Reads and parses the PSF file at `path`.
+    fn from_file(path: impl AsRef<std::path::Path>) -> Result<Self> {
+        Self::from_psf(&std::fs::read(path)?)
+    }
+}
+
+/// Returns `true` if `data` looks like an ASCII PSF file.
+pub fn is_ascii(data: &[u8]) -> bool {
+    data.trim_ascii_start().starts_with(b"HEADER")
+}
+
+macro_rules! impl_from_psf {
+    ($($t:ty),*) => {
+        $(
+            impl FromPsf for $t {
+                fn from_ascii_ast(ast: &AsciiAst) -> Result<Self> {
+                    Self::try_from_ascii(ast)
+                }
+
+                fn from_binary_ast(ast: BinaryAst) -> Result<Self> {
+                    Self::try_from_binary(ast)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_psf!(
+    ac::AcData,
+    noise::NoiseData,
+    pac::PnoiseData,
+    pac::SidebandData,
+    pss::PssFreqData,
+    pss::PssTimeData,
+    stb::StbData,
+    transient::TransientData,
+    xf::XfData
+);
+
+impl FromPsf for dc::DcData {
+    fn from_ascii_ast(ast: &AsciiAst) -> Result<Self> {
+        Self::try_from_ast(ast)
+    }
+
+    fn from_binary_ast(_ast: BinaryAst) -> Result<Self> {
+        bail!("reading dc analyses from binary PSF files is not supported")
+    }
+}
+
+impl FromPsf for sp::SpData {
+    fn from_ascii_ast(ast: &AsciiAst) -> Result<Self> {
+        Self::from_ascii(ast)
+    }
+
+    fn from_binary_ast(ast: BinaryAst) -> Result<Self> {
+        Self::from_binary(ast)
+    }
+}
@@ -0,0 +1,246 @@
+use std::path::Path;
+
+use anyhow::bail;
+
+use crate::analysis::FromPsf;
+use crate::ascii::ast::{PsfAst as AsciiAst, Values};
+use crate::Result;
+
+/// The name of the sweep Spectre uses for Monte Carlo iterations.
+pub const MC_SWEEP: &str = "mcparamset";
+
+/// The results of a Monte Carlo analysis, one per iteration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonteCarlo<T> {
+    /// The iteration number of each run.
+    pub iterations: Vec<i64>,
+    pub runs: Vec<T>,
+}
+
+/// A scalar measurement extracted from each Monte Carlo iteration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Measurement {
+    /// The iteration number of each value.
+    pub iterations: Vec<i64>,
+    /// The measured value of each iteration,
+    /// or `None` if the measurement failed for that iteration.
+    pub values: Vec<Option<f64>>,
+}
+
+/// Summary statistics of a [`Measurement`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct McStats {
+    /// The number of iterations with a successful measurement.
+    pub count: usize,
+    pub mean: f64,
+    /// The sample standard deviation.
+    pub std_dev: f64,
+    pub min: f64,
+    /// The iteration with the smallest value.
+    pub min_iteration: i64,
+    pub max: f64,
+    /// The iteration with the largest value.
+    pub max_iteration: i64,
+    /// The iterations for which the measurement failed.
+    pub failed: Vec<i64>,
+}
+
+/// A histogram of measured values.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    /// The `bins + 1` bin edges, in ascending order.
+    pub edges: Vec<f64>,
+    /// The number of values in each bin.
+    ///
+    /// Bins include their lower edge; the last bin also includes its upper edge.
+    pub counts: Vec<usize>,
+}
+
+/// The direction in which a measurement is considered worst.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Worst {
+    /// Smaller values are worse.
+    Min,
+    /// Larger values are worse.
+    Max,
+    /// Values farther from the target are worse.
+    FarthestFrom(f64),
+}
+
+impl<T> MonteCarlo<T> {
+    /// Wraps runs numbered `1..=runs.len()`, matching Spectre's iteration numbering.
+    pub fn from_runs(runs: Vec<T>) -> Self {
+        Self {
+            iterations: (1..=runs.len() as i64).collect(),
+            runs,
+        }
+    }
+
+    /// Extracts a scalar measurement from each run.
+    pub fn measure(&self, f: impl Fn(&T) -> Option<f64>) -> Measurement {
+        Measurement {
+            iterations: self.iterations.clone(),
+            values: self.runs.iter().map(f).collect(),
+        }
+    }
+}
+
+impl<T: FromPsf> MonteCarlo<T> {
+    /// Loads one PSF file per iteration, in order.
+    pub fn load<P: AsRef<Path>>(paths: impl IntoIterator<Item = P>) -> Result<Self> {
+        let runs = paths
+            .into_iter()
+            .map(T::from_file)
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self::from_runs(runs))
+    }
+
+    /// Splits an ASCII PSF family swept over `sweep` (typically [`MC_SWEEP`])
+    /// into one run per iteration.
+    pub fn from_ascii_family(ast: &AsciiAst, sweep: &str) -> Result<Self> {
+        let mut iterations = Vec::new();
+        let mut bounds = Vec::new();
+        for (i, v) in ast.values.iter().enumerate() {
+            if v.signal == sweep {
+                let Values::Real(values) = &v.values else {
+                    bail!("expected real iteration number; found complex");
+                };
+                iterations.push(values[0].round() as i64);
+                bounds.push(i);
+            }
+        }
+        bounds.push(ast.values.len());
+
+        let sweeps = ast
+            .sweeps
+            .iter()
+            .filter(|s| s.name != sweep)
+            .cloned()
+            .collect::<Vec<_>>();
+        let runs = bounds
+            .windows(2)
+            .map(|w| {
+                let run = AsciiAst {
+                    header: ast.header.clone(),
+                    types: ast.types.clone(),
+                    sweeps: sweeps.clone(),
+                    traces: ast.traces.clone(),
+                    values: ast.values[w[0] + 1..w[1]].to_vec(),
+                };
+                T::from_ascii_ast(&run)
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self { iterations, runs })
+    }
+}
+
+impl Measurement {
+    /// The successfully measured values, with their iteration numbers.
+    fn valid(&self) -> impl Iterator<Item = (i64, f64)> + '_ {
+        self.iterations
+            .iter()
+            .zip(self.values.iter())
+            .filter_map(|(i, v)| Some((*i, (*v)?)))
+    }
+
+    /// The successfully measured values, sorted in ascending order.
+    fn sorted(&self) -> Vec<f64> {
+        let mut values = self.valid().map(|(_, v)| v).collect::<Vec<_>>();
+        values.sort_by(f64::total_cmp);
+        values
+    }
+
+    /// Computes summary statistics.
+    ///
+    /// Returns `None` if no iteration was measured successfully.
+    pub fn stats(&self) -> Option<McStats> {
+        let (mut min_iteration, mut min) = self.valid().next()?;
+        let (mut max_iteration, mut max) = (min_iteration, min);
+        let (mut count, mut sum) = (0, 0.);
+        for (i, v) in self.valid() {
+            count += 1;
+            sum += v;
+            if v < min {
+                (min_iteration, min) = (i, v);
+            }
+            if v > max {
+                (max_iteration, max) = (i, v);
+            }
+        }
+        let mean = sum / count as f64;
+        let std_dev = if count > 1 {
+            let ss = self.valid().map(|(_, v)| (v - mean).powi(2)).sum::<f64>();
+            (ss / (count - 1) as f64).sqrt()
+        } else {
+            0.
+        };
+        let failed = self
+            .iterations
+            .iter()
+            .zip(self.values.iter())
+            .filter(|(_, v)| v.is_none())
+            .map(|(i, _)| *i)
+            .collect();
+
+        Some(McStats {
+            count,
+            mean,
+            std_dev,
+            min,
+            min_iteration,
+            max,
+            max_iteration,
+            failed,
+        })
+    }
+
+    /// Computes the `q`-quantile, for `q` in `[0, 1]`,
+    /// interpolating linearly between order statistics.
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        let sorted = self.sorted();
+        if sorted.is_empty() || !(0. ..=1.).contains(&q) {
+            return None;
+        }
+        let pos = q * (sorted.len() - 1) as f64;
+        let (lo, hi) = (pos.floor() as usize, pos.ceil() as usize);
+        Some(sorted[lo] + (pos - lo as f64) * (sorted[hi] - sorted[lo]))
+    }
+
+    /// Computes the quantile for each of `qs`.
+    pub fn quantiles(&self, qs: &[f64]) -> Option<Vec<f64>> {
+        qs.iter().map(|q| self.quantile(*q)).collect()
+    }
+
+    /// Bins the measured values into `bins` equal-width bins spanning their range.
+    pub fn histogram(&self, bins: usize) -> Option<Histogram> {
+        let sorted = self.sorted();
+        let (min, max) = (*sorted.first()?, *sorted.last()?);
+        if bins == 0 {
+            return None;
+        }
+        let width = (max - min) / bins as f64;
+        let edges = (0..=bins).map(|i| min + i as f64 * width).collect();
+        let mut counts = vec![0; bins];
+        for v in sorted {
+            let bin = if width > 0. {
+                (((v - min) / width) as usize).min(bins - 1)
+            } else {
+                0
+            };
+            counts[bin] += 1;
+        }
+        Some(Histogram { edges, counts })
+    }
+
+    /// Finds the iteration with the worst measured value.
+    pub fn worst_case(&self, worst: Worst) -> Option<(i64, f64)> {
+        let badness = |v: f64| match worst {
+            Worst::Min => -v,
+            Worst::Max => v,
+            Worst::FarthestFrom(target) => (v - target).abs(),
+        };
+        self.valid()
+            .max_by(|a, b| badness(a.1).total_cmp(&badness(b.1)))
+    }
+}
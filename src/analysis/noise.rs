@@ -0,0 +1,278 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail};
+
+use crate::analysis::transient::{trapz, window};
+use crate::ascii::ast::{Kind, PsfAst as AsciiAst, Trace, Values};
+use crate::binary::ast::PsfAst as BinaryAst;
+use crate::Result;
+
+/// The name of the output noise signal written by Spectre.
+pub const OUTPUT_NOISE: &str = "out";
+/// The name of the input-referred noise signal written by Spectre.
+pub const INPUT_NOISE: &str = "in";
+/// The name of the struct field containing a device's total noise contribution.
+const TOTAL: &str = "total";
+
+/// Results of a Spectre `noise` analysis.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NoiseData {
+    pub freq: Vec<f64>,
+    /// The output noise spectral density, in units such as `V/sqrt(Hz)`.
+    pub output: Vec<f64>,
+    /// The input-referred noise spectral density, if an input source was specified.
+    pub input: Option<Vec<f64>>,
+    /// Maps each device to the power spectral densities (e.g. `V^2/Hz`)
+    /// of its noise sources at the output.
+    pub contributions: HashMap<String, HashMap<String, Vec<f64>>>,
+}
+
+/// The physical origin of a noise source.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub enum NoiseKind {
+    Thermal,
+    Flicker,
+    Shot,
+    Other,
+}
+
+impl NoiseKind {
+    /// Classifies a noise source based on the field name Spectre uses for it.
+    pub fn from_source(source: &str) -> Self {
+        let source = source.to_ascii_lowercase();
+        if source.starts_with("fn") || source.contains("flicker") {
+            Self::Flicker
+        } else if source.contains("shot") {
+            Self::Shot
+        } else if source.starts_with('r') || source == "id" || source.contains("thermal") {
+            Self::Thermal
+        } else {
+            Self::Other
+        }
+    }
+}
+
+/// The noise contributed by one source of one device over a frequency band.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contributor {
+    pub device: String,
+    pub source: String,
+    pub kind: NoiseKind,
+    /// The integrated noise power at the output, e.g. in `V^2`.
+    pub power: f64,
+}
+
+impl NoiseData {
+    /// Reads noise data from an ASCII PSF.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the file is not a valid noise analysis; see [`Self::try_from_ascii`].
+    pub fn from_ascii(ast: &AsciiAst) -> Self {
+        Self::try_from_ascii(ast).unwrap()
+    }
+
+    /// Reads noise data from an ASCII PSF.
+    ///
+    /// Fails if the output noise is missing or any signal has unexpected values.
+    pub fn try_from_ascii(ast: &AsciiAst) -> Result<Self> {
+        // type name -> struct field names
+        let mut structs = HashMap::<&str, Vec<&str>>::new();
+        for ty in ast.types.iter() {
+            for kind in ty.kinds.iter() {
+                if let Kind::Struct(fields) = kind {
+                    structs.insert(ty.name, fields.iter().map(|f| f.name).collect());
+                }
+            }
+        }
+
+        // signal name -> struct field names
+        let mut fields = HashMap::<&str, &[&str]>::new();
+        for trace in ast.traces.iter() {
+            if let Trace::Signal { name, units } = trace {
+                if let Some(names) = structs.get(units) {
+                    fields.insert(name, names);
+                }
+            }
+        }
+
+        let mut freq = Vec::new();
+        let mut signals = HashMap::<&str, Vec<f64>>::new();
+        let mut contributions = HashMap::<String, HashMap<String, Vec<f64>>>::new();
+        for v in ast.values.iter() {
+            match &v.values {
+                Values::Real(values) => {
+                    debug_assert_eq!(values.len(), 1);
+                    if v.signal == "freq" {
+                        freq.push(values[0]);
+                    } else {
+                        signals.entry(v.signal).or_default().push(values[0]);
+                    }
+                }
+                Values::Struct(values) => {
+                    debug_assert_eq!(values.len(), 1);
+                    let Some(names) = fields.get(v.signal) else {
+                        bail!("signal `{}` has struct values but no struct type", v.signal);
+                    };
+                    let device = contributions.entry(v.signal.to_string()).or_default();
+                    for (name, x) in names.iter().zip(values[0].iter()) {
+                        device.entry(name.to_string()).or_default().push(*x);
+                    }
+                }
+                Values::Complex(_) => {
+                    bail!(
+                        "expected real or struct values for signal `{}`; found complex",
+                        v.signal
+                    )
+                }
+                Values::Mixed(_) => bail!(
+                    "expected real or struct values for signal `{}`; found non-numeric struct",
+                    v.signal
+                ),
+            }
+        }
+
+        let output = signals
+            .remove(OUTPUT_NOISE)
+            .ok_or_else(|| anyhow!("missing output noise signal `{OUTPUT_NOISE}`"))?;
+        let input = signals.remove(INPUT_NOISE);
+
+        // Devices without struct types report a single total contribution.
+        for (name, values) in signals {
+            contributions
+                .entry(name.to_string())
+                .or_default()
+                .insert(TOTAL.to_string(), values);
+        }
+
+        Ok(Self {
+            freq,
+            output,
+            input,
+            contributions,
+        })
+    }
+
+    /// Reads noise data from a binary PSF.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the file is not a valid noise analysis; see [`Self::try_from_binary`].
+    pub fn from_binary(ast: BinaryAst) -> Self {
+        Self::try_from_binary(ast).unwrap()
+    }
+
+    /// Reads noise data from a binary PSF.
+    ///
+    /// The binary parser does not support struct-typed traces, so only
+    /// per-device totals saved as real traces are available.
+    /// Fails if the file is not swept over frequency, the output noise is missing,
+    /// or any signal is missing or complex.
+    pub fn try_from_binary(mut ast: BinaryAst) -> Result<Self> {
+        let Some(sweep) = ast.sweeps.first() else {
+            bail!("noise analysis has no sweep");
+        };
+        if sweep.name != "freq" {
+            bail!(
+                "noise analysis expects to sweep frequency; found `{}`",
+                sweep.name
+            );
+        }
+        let freq = ast.values.take_real(sweep)?;
+
+        let mut signals = HashMap::new();
+        for group in ast.traces.iter() {
+            for sig in group.signals() {
+                signals.insert(sig.name.to_string(), ast.values.take_real(sig)?);
+            }
+        }
+
+        let output = signals
+            .remove(OUTPUT_NOISE)
+            .ok_or_else(|| anyhow!("missing output noise signal `{OUTPUT_NOISE}`"))?;
+        let input = signals.remove(INPUT_NOISE);
+        let contributions = signals
+            .into_iter()
+            .map(|(name, values)| (name, HashMap::from([(TOTAL.to_string(), values)])))
+            .collect();
+
+        Ok(Self {
+            freq,
+            output,
+            input,
+            contributions,
+        })
+    }
+
+    /// Integrates a power spectral density over `[f1, f2]` using the trapezoidal rule.
+    fn integrate_psd(&self, psd: &[f64], f1: f64, f2: f64) -> f64 {
+        match window(&self.freq, psd, f1, f2) {
+            Some((f, x)) => trapz(&f, &x),
+            None => 0.,
+        }
+    }
+
+    /// Integrates a spectral density over `[f1, f2]`, returning the RMS value.
+    fn integrate_density(&self, density: &[f64], f1: f64, f2: f64) -> f64 {
+        let psd = density.iter().map(|x| x * x).collect::<Vec<_>>();
+        self.integrate_psd(&psd, f1, f2).sqrt()
+    }
+
+    /// The total RMS output noise over the band `[f1, f2]`.
+    pub fn integrate(&self, f1: f64, f2: f64) -> f64 {
+        self.integrate_density(&self.output, f1, f2)
+    }
+
+    /// The total RMS input-referred noise over the band `[f1, f2]`.
+    pub fn integrate_input(&self, f1: f64, f2: f64) -> Option<f64> {
+        Some(self.integrate_density(self.input.as_ref()?, f1, f2))
+    }
+
+    /// Lists the noise sources of every device, ranked by their output noise power
+    /// over the band `[f1, f2]`, largest first.
+    ///
+    /// Device totals are only listed for devices that do not report individual sources.
+    pub fn contributors(&self, f1: f64, f2: f64) -> Vec<Contributor> {
+        let mut contributors = Vec::new();
+        for (device, sources) in self.contributions.iter() {
+            let has_sources = sources.keys().any(|s| s != TOTAL);
+            for (source, psd) in sources.iter() {
+                if has_sources && source == TOTAL {
+                    continue;
+                }
+                contributors.push(Contributor {
+                    device: device.clone(),
+                    source: source.clone(),
+                    kind: NoiseKind::from_source(source),
+                    power: self.integrate_psd(psd, f1, f2),
+                });
+            }
+        }
+        contributors.sort_by(|a, b| b.power.total_cmp(&a.power));
+        contributors
+    }
+
+    /// Ranks devices by their total output noise power over the band `[f1, f2]`,
+    /// largest first.
+    pub fn device_totals(&self, f1: f64, f2: f64) -> Vec<(String, f64)> {
+        let mut totals = HashMap::<String, f64>::new();
+        for c in self.contributors(f1, f2) {
+            *totals.entry(c.device).or_default() += c.power;
+        }
+        let mut totals = totals.into_iter().collect::<Vec<_>>();
+        totals.sort_by(|a, b| b.1.total_cmp(&a.1));
+        totals
+    }
+
+    /// Ranks noise kinds by their total output noise power over the band `[f1, f2]`,
+    /// largest first.
+    pub fn kind_totals(&self, f1: f64, f2: f64) -> Vec<(NoiseKind, f64)> {
+        let mut totals = HashMap::<NoiseKind, f64>::new();
+        for c in self.contributors(f1, f2) {
+            *totals.entry(c.kind).or_default() += c.power;
+        }
+        let mut totals = totals.into_iter().collect::<Vec<_>>();
+        totals.sort_by(|a, b| b.1.total_cmp(&a.1));
+        totals
+    }
+}
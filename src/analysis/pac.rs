@@ -0,0 +1,215 @@
+use std::collections::BTreeMap;
+
+use anyhow::bail;
+use num::complex::Complex64;
+
+use crate::analysis::ac::{AcData, Probe};
+use crate::analysis::noise::NoiseData;
+use crate::ascii::ast::{PsfAst as AsciiAst, Value as AsciiValue, Values};
+use crate::binary::ast::{PsfAst as BinaryAst, Value as BinaryValue};
+use crate::Result;
+
+/// The name of the header value giving the sideband of files
+/// containing a single sideband.
+const SIDEBAND: &str = "sideband";
+
+/// Complex small-signal results swept over frequency for each sideband,
+/// as produced by periodic small-signal analyses such as `pac` and `pxf`.
+///
+/// Sideband `k` refers to the frequency `f + k * f0`,
+/// where `f` is the swept frequency and `f0` is the PSS fundamental.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SidebandData {
+    pub sidebands: BTreeMap<i64, AcData>,
+}
+
+/// Results of a periodic AC (`pac`) analysis.
+pub type PacData = SidebandData;
+
+/// Results of a periodic transfer function (`pxf`) analysis.
+pub type PxfData = SidebandData;
+
+impl SidebandData {
+    /// Reads an ASCII PSF containing one or more sidebands.
+    ///
+    /// The sideband index is given by the values of a sweep other than `freq`.
+    /// Files without such a sweep contain the single sideband
+    /// given by their `sideband` header value, or sideband 0 if there is none.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the file is not a valid sideband analysis;
+    /// see [`Self::try_from_ascii`].
+    pub fn from_ascii(ast: &AsciiAst) -> Self {
+        Self::try_from_ascii(ast).unwrap()
+    }
+
+    /// Reads an ASCII PSF containing one or more sidebands.
+    ///
+    /// Fails if a sideband index is not real, or any signal is not complex.
+    pub fn try_from_ascii(ast: &AsciiAst) -> Result<Self> {
+        let groups = ast.groups();
+
+        let sideband_sweep = ast
+            .sweeps
+            .iter()
+            .map(|s| s.name)
+            .find(|name| *name != "freq");
+
+        let mut sidebands = BTreeMap::new();
+        let mut current = ast
+            .header
+            .values
+            .iter()
+            .find(|v| v.name == SIDEBAND)
+            .and_then(|v| match v.value {
+                AsciiValue::Int(k) => Some(k),
+                _ => None,
+            })
+            .unwrap_or(0);
+        let mut start = 0;
+        for (i, v) in ast.values.iter().enumerate() {
+            if Some(v.signal) == sideband_sweep {
+                if start < i {
+                    let data =
+                        AcData::from_ascii_values(&groups, "freq", ast.values[start..i].iter())?;
+                    sidebands.insert(current, data);
+                }
+                let Values::Real(values) = &v.values else {
+                    bail!("expected real sideband index; found complex");
+                };
+                current = values[0].round() as i64;
+                start = i + 1;
+            }
+        }
+        if start < ast.values.len() {
+            let data = AcData::from_ascii_values(&groups, "freq", ast.values[start..].iter())?;
+            sidebands.insert(current, data);
+        }
+        let units = ast.signal_units();
+        for data in sidebands.values_mut() {
+            data.units = units.clone();
+        }
+
+        Ok(Self { sidebands })
+    }
+
+    /// Reads a binary PSF containing a single sideband.
+    ///
+    /// The sideband is given by the file's `sideband` header value,
+    /// or is sideband 0 if there is none.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the file is not a valid sideband analysis;
+    /// see [`Self::try_from_binary`].
+    pub fn from_binary(ast: BinaryAst) -> Self {
+        Self::try_from_binary(ast).unwrap()
+    }
+
+    /// Reads a binary PSF containing a single sideband.
+    pub fn try_from_binary(ast: BinaryAst) -> Result<Self> {
+        let sideband = match ast.header.values.get(SIDEBAND) {
+            Some(BinaryValue::Int(k)) => *k,
+            _ => 0,
+        };
+        Ok(Self {
+            sidebands: BTreeMap::from([(sideband, AcData::try_from_binary(ast)?)]),
+        })
+    }
+
+    /// Gets the results for sideband `k`.
+    #[inline]
+    pub fn sideband(&self, k: i64) -> Option<&AcData> {
+        self.sidebands.get(&k)
+    }
+
+    /// Gets the values of a quantity in sideband `k`.
+    pub fn probe<'a>(&self, k: i64, probe: impl Into<Probe<'a>>) -> Option<Vec<Complex64>> {
+        self.sideband(k)?.probe(probe)
+    }
+
+    /// Gets the value of a quantity in sideband `k` at the frequency point `idx`.
+    pub fn get(&self, k: i64, name: &str, idx: usize) -> Option<Complex64> {
+        self.sideband(k)?.signal(name)?.get(idx).copied()
+    }
+
+    /// Computes the conversion gain from `input` in sideband `in_sideband`
+    /// to `output` in sideband `out_sideband` at each swept frequency.
+    ///
+    /// For a downconverting mixer driven at the RF input,
+    /// the input is typically in sideband 0 and the output in sideband -1.
+    pub fn conversion_gain<'a, 'b>(
+        &self,
+        output: impl Into<Probe<'a>>,
+        out_sideband: i64,
+        input: impl Into<Probe<'b>>,
+        in_sideband: i64,
+    ) -> Option<Vec<Complex64>> {
+        let output = self.probe(out_sideband, output)?;
+        let input = self.probe(in_sideband, input)?;
+        Some(
+            output
+                .iter()
+                .zip(input.iter())
+                .map(|(o, i)| o / i)
+                .collect(),
+        )
+    }
+}
+
+/// Results of a periodic noise (`pnoise`) analysis.
+///
+/// The output noise includes contributions folded from all sidebands.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PnoiseData {
+    pub data: NoiseData,
+}
+
+impl PnoiseData {
+    /// Reads periodic noise data from an ASCII PSF.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the file is not a valid noise analysis; see [`Self::try_from_ascii`].
+    pub fn from_ascii(ast: &AsciiAst) -> Self {
+        Self::try_from_ascii(ast).unwrap()
+    }
+
+    /// Reads periodic noise data from an ASCII PSF.
+    pub fn try_from_ascii(ast: &AsciiAst) -> Result<Self> {
+        Ok(Self {
+            data: NoiseData::try_from_ascii(ast)?,
+        })
+    }
+
+    /// Reads periodic noise data from a binary PSF.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the file is not a valid noise analysis; see [`Self::try_from_binary`].
+    pub fn from_binary(ast: BinaryAst) -> Self {
+        Self::try_from_binary(ast).unwrap()
+    }
+
+    /// Reads periodic noise data from a binary PSF.
+    pub fn try_from_binary(ast: BinaryAst) -> Result<Self> {
+        Ok(Self {
+            data: NoiseData::try_from_binary(ast)?,
+        })
+    }
+
+    /// The single-sideband phase noise at each offset frequency, in dBc/Hz,
+    /// for a carrier of peak amplitude `carrier_amplitude`.
+    ///
+    /// Assumes the output noise near the carrier is dominated by phase noise,
+    /// so that `L(f) = S_v(f) / A^2`, where `S_v` is the output noise power
+    /// spectral density.
+    pub fn phase_noise_dbc(&self, carrier_amplitude: f64) -> Vec<f64> {
+        self.data
+            .output
+            .iter()
+            .map(|v| 10. * (v * v / (carrier_amplitude * carrier_amplitude)).log10())
+            .collect()
+    }
+}
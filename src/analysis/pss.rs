@@ -0,0 +1,316 @@
+use std::collections::HashMap;
+use std::f64::consts::PI;
+
+use anyhow::bail;
+use num::complex::Complex64;
+#[cfg(feature = "fft")]
+use rustfft::FftPlanner;
+
+use crate::analysis::ac::AcData;
+#[cfg(feature = "fft")]
+use crate::analysis::transient::interp;
+use crate::analysis::transient::TransientData;
+use crate::ascii::ast::{PsfAst as AsciiAst, Value as AsciiValue};
+use crate::binary::ast::{PsfAst as BinaryAst, Value as BinaryValue};
+use crate::Result;
+
+/// Time-domain results of a periodic steady-state analysis (`.td.pss`).
+///
+/// The waveforms span exactly one period of the steady-state solution.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PssTimeData {
+    pub data: TransientData,
+}
+
+/// Frequency-domain results of a periodic steady-state or
+/// harmonic balance analysis (`.fd.pss`, `.fd.hb`).
+///
+/// Each signal holds one phasor per harmonic, such that the periodic waveform is
+/// `x(t) = Re(X[0]) + sum over k >= 1 of Re(X[k] exp(j k w0 t))`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PssFreqData {
+    /// The harmonic index of each point.
+    pub harmonics: Vec<i64>,
+    /// The fundamental frequency, in Hz, if known.
+    ///
+    /// Files swept over harmonic index only specify the fundamental
+    /// through a `fundamental` header value.
+    pub fundamental: Option<f64>,
+    /// The phasors of each signal, one per harmonic.
+    pub signals: HashMap<String, Vec<Complex64>>,
+    /// The units of each signal, where known.
+    pub units: HashMap<String, String>,
+}
+
+/// The name of the header value giving the fundamental frequency
+/// of files swept over harmonic index.
+const FUNDAMENTAL: &str = "fundamental";
+
+impl PssTimeData {
+    /// Reads time-domain PSS data from an ASCII PSF.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the file is not a valid time-domain PSS analysis;
+    /// see [`Self::try_from_ascii`].
+    pub fn from_ascii(ast: &AsciiAst) -> Self {
+        Self::try_from_ascii(ast).unwrap()
+    }
+
+    /// Reads time-domain PSS data from an ASCII PSF.
+    pub fn try_from_ascii(ast: &AsciiAst) -> Result<Self> {
+        Ok(Self {
+            data: TransientData::try_from_ascii(ast)?,
+        })
+    }
+
+    /// Reads time-domain PSS data from a binary PSF.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the file is not a valid time-domain PSS analysis;
+    /// see [`Self::try_from_binary`].
+    pub fn from_binary(ast: BinaryAst) -> Self {
+        Self::try_from_binary(ast).unwrap()
+    }
+
+    /// Reads time-domain PSS data from a binary PSF.
+    pub fn try_from_binary(ast: BinaryAst) -> Result<Self> {
+        Ok(Self {
+            data: TransientData::try_from_binary(ast)?,
+        })
+    }
+
+    #[inline]
+    pub fn time(&self) -> Option<&Vec<f64>> {
+        self.data.signal(&self.data.time)
+    }
+
+    #[inline]
+    pub fn signal(&self, name: &str) -> Option<&Vec<f64>> {
+        self.data.signal(name)
+    }
+
+    /// The period of the steady-state solution, in seconds.
+    pub fn period(&self) -> Option<f64> {
+        let time = self.time()?;
+        Some(time.last()? - time.first()?)
+    }
+
+    /// Computes the phasors of harmonics `0..=harmonics` of every signal.
+    ///
+    /// Each waveform is linearly interpolated onto a uniform grid over one period
+    /// before taking its discrete Fourier transform.
+    /// Requires the `fft` feature.
+    #[cfg(feature = "fft")]
+    pub fn to_freq(&self, harmonics: usize) -> Option<PssFreqData> {
+        let time = self.time()?;
+        let period = self.period()?;
+        if period <= 0. {
+            return None;
+        }
+        let t0 = time[0];
+        let n = (time.len() - 1).max(2 * harmonics + 1);
+        let mut fft = FftPlanner::new();
+        let fft = fft.plan_fft_forward(n);
+
+        let mut signals = HashMap::new();
+        for (name, values) in self.data.signals.iter() {
+            if *name == self.data.time {
+                continue;
+            }
+            let mut buf = (0..n)
+                .map(|i| {
+                    let t = t0 + period * i as f64 / n as f64;
+                    Complex64::new(interp(time, values, t), 0.)
+                })
+                .collect::<Vec<_>>();
+            fft.process(&mut buf);
+            let phasors = (0..=harmonics)
+                .map(|k| {
+                    // Shift the phase reference to t = 0.
+                    let shift = Complex64::from_polar(1., 2. * PI * k as f64 * t0 / period);
+                    let scale = if k == 0 { 1. } else { 2. } / n as f64;
+                    buf[k % n] * scale * shift.conj()
+                })
+                .collect();
+            signals.insert(name.clone(), phasors);
+        }
+
+        let mut units = self.data.units.clone();
+        units.remove(&self.data.time);
+        Some(PssFreqData {
+            harmonics: (0..=harmonics as i64).collect(),
+            fundamental: Some(1. / period),
+            signals,
+            units,
+        })
+    }
+}
+
+impl PssFreqData {
+    /// Wraps data swept over harmonic frequencies.
+    ///
+    /// The fundamental is taken to be the smallest nonzero frequency.
+    pub fn from_ac(data: AcData) -> Self {
+        let fundamental = data
+            .freq
+            .iter()
+            .copied()
+            .filter(|f| *f > 0.)
+            .min_by(f64::total_cmp);
+        let harmonics = data
+            .freq
+            .iter()
+            .map(|f| fundamental.map_or(0, |f0| (f / f0).round() as i64))
+            .collect();
+        let mut units = data.units;
+        units.remove("freq");
+        Self {
+            harmonics,
+            fundamental,
+            signals: data.signals,
+            units,
+        }
+    }
+
+    /// Wraps data read from a sweep named `sweep`, which holds
+    /// either frequencies (if named `freq`) or harmonic indices.
+    fn from_sweep(mut data: AcData, sweep: &str, fundamental: Option<f64>) -> Self {
+        if sweep == "freq" {
+            return Self::from_ac(data);
+        }
+        data.units.remove(sweep);
+        Self {
+            harmonics: data.freq.iter().map(|k| k.round() as i64).collect(),
+            fundamental,
+            signals: data.signals,
+            units: data.units,
+        }
+    }
+
+    /// Reads frequency-domain PSS data from an ASCII PSF.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the file is not a valid frequency-domain PSS analysis;
+    /// see [`Self::try_from_ascii`].
+    pub fn from_ascii(ast: &AsciiAst) -> Self {
+        Self::try_from_ascii(ast).unwrap()
+    }
+
+    /// Reads frequency-domain PSS data from an ASCII PSF.
+    pub fn try_from_ascii(ast: &AsciiAst) -> Result<Self> {
+        let sweep = ast.sweeps.first().map_or("freq", |s| s.name);
+        let fundamental = ast
+            .header
+            .values
+            .iter()
+            .find(|v| v.name == FUNDAMENTAL)
+            .and_then(|v| match v.value {
+                AsciiValue::Real(x) => Some(x),
+                AsciiValue::Int(x) => Some(x as f64),
+                _ => None,
+            });
+        let data = AcData::from_ascii_sweep(ast, sweep)?;
+        Ok(Self::from_sweep(data, sweep, fundamental))
+    }
+
+    /// Reads frequency-domain PSS data from a binary PSF.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the file is not a valid frequency-domain PSS analysis;
+    /// see [`Self::try_from_binary`].
+    pub fn from_binary(ast: BinaryAst) -> Self {
+        Self::try_from_binary(ast).unwrap()
+    }
+
+    /// Reads frequency-domain PSS data from a binary PSF.
+    pub fn try_from_binary(ast: BinaryAst) -> Result<Self> {
+        let Some(sweep) = ast.sweeps.first() else {
+            bail!("pss analysis has no sweep");
+        };
+        let sweep = sweep.name.to_string();
+        let fundamental = ast.header.values.get(FUNDAMENTAL).and_then(|v| match v {
+            BinaryValue::Real(x) => Some(*x),
+            BinaryValue::Int(x) => Some(*x as f64),
+            _ => None,
+        });
+        let data = AcData::from_binary_sweep(ast)?;
+        Ok(Self::from_sweep(data, &sweep, fundamental))
+    }
+
+    #[inline]
+    pub fn signal(&self, name: &str) -> Option<&Vec<Complex64>> {
+        self.signals.get(name)
+    }
+
+    /// The frequency of each harmonic, if the fundamental is known.
+    pub fn freq(&self) -> Option<Vec<f64>> {
+        let f0 = self.fundamental?;
+        Some(self.harmonics.iter().map(|k| *k as f64 * f0).collect())
+    }
+
+    /// The phasor of harmonic `k` of the signal `name`.
+    pub fn harmonic(&self, name: &str, k: i64) -> Option<Complex64> {
+        let idx = self.harmonics.iter().position(|h| *h == k)?;
+        Some(self.signal(name)?[idx])
+    }
+
+    /// The magnitude of harmonic `k` of the signal `name`.
+    pub fn harmonic_mag(&self, name: &str, k: i64) -> Option<f64> {
+        Some(self.harmonic(name, k)?.norm())
+    }
+
+    /// The magnitude of harmonic `k` of the signal `name`, in dB.
+    pub fn harmonic_db20(&self, name: &str, k: i64) -> Option<f64> {
+        Some(20. * self.harmonic_mag(name, k)?.log10())
+    }
+
+    /// Reconstructs the periodic waveforms of every signal
+    /// at `points + 1` uniformly spaced times spanning one period.
+    ///
+    /// Negative harmonics are ignored.
+    /// Returns `None` if the fundamental is not known.
+    pub fn to_time(&self, points: usize) -> Option<PssTimeData> {
+        let fundamental = self.fundamental.filter(|f| *f > 0.)?;
+        if points == 0 {
+            return None;
+        }
+        let period = 1. / fundamental;
+        let time = (0..=points)
+            .map(|i| period * i as f64 / points as f64)
+            .collect::<Vec<_>>();
+
+        let mut signals = HashMap::new();
+        for (name, phasors) in self.signals.iter() {
+            let values = time
+                .iter()
+                .map(|t| {
+                    self.harmonics
+                        .iter()
+                        .zip(phasors.iter())
+                        .filter(|(k, _)| **k >= 0)
+                        .map(|(&k, x)| {
+                            let w = 2. * PI * k as f64 * fundamental;
+                            (x * Complex64::from_polar(1., w * t)).re
+                        })
+                        .sum()
+                })
+                .collect();
+            signals.insert(name.clone(), values);
+        }
+        signals.insert("time".to_string(), time);
+        let mut units = self.units.clone();
+        units.insert("time".to_string(), "s".to_string());
+
+        Some(PssTimeData {
+            data: TransientData {
+                signals,
+                time: "time".to_string(),
+                units,
+            },
+        })
+    }
+}
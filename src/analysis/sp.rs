@@ -0,0 +1,356 @@
+use std::fmt::Write as _;
+use std::io::Write;
+
+use anyhow::{anyhow, bail};
+use num::complex::Complex64;
+
+use crate::analysis::ac::AcData;
+use crate::ascii::ast::{PsfAst as AsciiAst, Value as AsciiValue};
+use crate::binary::ast::{PsfAst as BinaryAst, Value as BinaryValue};
+use crate::Result;
+
+/// Results of a Spectre S-parameter (`sp`) analysis.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpData {
+    pub freq: Vec<f64>,
+    /// The number of ports.
+    pub ports: usize,
+    /// The real reference impedance of each port, in ohms.
+    pub z0: Vec<f64>,
+    /// The S-matrix at each frequency, stored in row-major order.
+    pub s: Vec<Vec<Complex64>>,
+}
+
+/// The format of complex numbers in a Touchstone file.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub enum TouchstoneFormat {
+    /// Real and imaginary parts.
+    #[default]
+    RealImag,
+    /// Linear magnitude and angle in degrees.
+    MagAngle,
+    /// Magnitude in dB and angle in degrees.
+    DbAngle,
+}
+
+impl TouchstoneFormat {
+    fn option(&self) -> &'static str {
+        match self {
+            Self::RealImag => "RI",
+            Self::MagAngle => "MA",
+            Self::DbAngle => "DB",
+        }
+    }
+
+    fn split(&self, x: Complex64) -> (f64, f64) {
+        match self {
+            Self::RealImag => (x.re, x.im),
+            Self::MagAngle => (x.norm(), x.arg().to_degrees()),
+            Self::DbAngle => (20. * x.norm().log10(), x.arg().to_degrees()),
+        }
+    }
+}
+
+/// Parses an S-parameter signal name such as `s21`, `s10_2` or `s(10,2)`
+/// into 1-based row and column port indices.
+fn parse_sparam_name(name: &str) -> Option<(usize, usize)> {
+    let rest = name.strip_prefix('s').or_else(|| name.strip_prefix('S'))?;
+    let rest = rest
+        .strip_prefix('(')
+        .and_then(|r| r.strip_suffix(')'))
+        .unwrap_or(rest);
+    let (i, j) = match rest.split_once(['_', ',']) {
+        Some((i, j)) => (i.trim().parse().ok()?, j.trim().parse().ok()?),
+        None if rest.len() == 2 && rest.bytes().all(|b| b.is_ascii_digit()) => {
+            let b = rest.as_bytes();
+            ((b[0] - b'0') as usize, (b[1] - b'0') as usize)
+        }
+        None => return None,
+    };
+    (i > 0 && j > 0).then_some((i, j))
+}
+
+/// Parses the name of a reference impedance header value.
+///
+/// Returns `Some(Some(port))` for a per-port value such as `z0_2` or `z0(2)`,
+/// with a 1-based port index, and `Some(None)` for the shared value `z0`.
+fn parse_z0_name(name: &str) -> Option<Option<usize>> {
+    let rest = name
+        .strip_prefix("z0")
+        .or_else(|| name.strip_prefix("Z0"))?;
+    if rest.is_empty() {
+        return Some(None);
+    }
+    let port = rest
+        .strip_prefix('(')
+        .and_then(|r| r.strip_suffix(')'))
+        .or_else(|| rest.strip_prefix('_'))?;
+    let port = port.trim().parse().ok()?;
+    (port > 0).then_some(Some(port))
+}
+
+/// Resolves the reference impedance of each port from header values.
+///
+/// Per-port values such as `z0_2` take precedence over a shared `z0` value.
+/// Fails if a port has no reference impedance.
+fn port_impedances<'a>(
+    values: impl IntoIterator<Item = (&'a str, Option<f64>)>,
+    ports: usize,
+) -> Result<Vec<f64>> {
+    let mut shared = None;
+    let mut z0 = vec![None; ports];
+    for (name, value) in values {
+        let Some(port) = parse_z0_name(name) else {
+            continue;
+        };
+        let value = value.ok_or_else(|| anyhow!("reference impedance `{name}` is not a number"))?;
+        match port {
+            None => shared = Some(value),
+            Some(port) if port <= ports => z0[port - 1] = Some(value),
+            Some(port) => bail!("reference impedance given for port {port} of {ports}"),
+        }
+    }
+    z0.into_iter()
+        .enumerate()
+        .map(|(i, z)| {
+            z.or(shared)
+                .ok_or_else(|| anyhow!("missing reference impedance for port {}", i + 1))
+        })
+        .collect()
+}
+
+/// Counts the ports of an AC-like dataset from the names of its S-parameter signals.
+fn count_ports(data: &AcData) -> usize {
+    data.signals
+        .keys()
+        .filter_map(|name| parse_sparam_name(name))
+        .map(|(i, j)| i.max(j))
+        .max()
+        .unwrap_or(0)
+}
+
+impl SpData {
+    /// Collects the S-parameter signals of an AC-like dataset into S-matrices,
+    /// with the given reference impedance for each port.
+    ///
+    /// Signals that are not named like S-parameters (e.g. `s21`) are ignored.
+    /// Missing matrix entries are set to zero.
+    /// Fails if the number of impedances does not match the number of ports.
+    pub fn from_ac(data: &AcData, z0: &[f64]) -> Result<Self> {
+        let ports = count_ports(data);
+        if z0.len() != ports {
+            bail!("expected {ports} reference impedances, got {}", z0.len());
+        }
+
+        let mut s = vec![vec![Complex64::new(0., 0.); ports * ports]; data.freq.len()];
+        for (name, values) in data.signals.iter() {
+            let Some((i, j)) = parse_sparam_name(name) else {
+                continue;
+            };
+            for (k, x) in values.iter().enumerate() {
+                s[k][(i - 1) * ports + (j - 1)] = *x;
+            }
+        }
+
+        Ok(Self {
+            freq: data.freq.clone(),
+            ports,
+            z0: z0.to_vec(),
+            s,
+        })
+    }
+
+    /// Reads S-parameters from an ASCII PSF.
+    ///
+    /// The reference impedance of each port is taken from a per-port header value
+    /// such as `z0_2` or `z0(2)`, or from a shared `z0` header value otherwise.
+    /// Fails if any port has no reference impedance.
+    pub fn from_ascii(ast: &AsciiAst) -> Result<Self> {
+        let data = AcData::try_from_ascii(ast)?;
+        let z0 = port_impedances(
+            ast.header.values.iter().map(|v| {
+                let value = match v.value {
+                    AsciiValue::Real(x) => Some(x),
+                    AsciiValue::Int(x) => Some(x as f64),
+                    _ => None,
+                };
+                (v.name, value)
+            }),
+            count_ports(&data),
+        )?;
+        Self::from_ac(&data, &z0)
+    }
+
+    /// Reads S-parameters from a binary PSF.
+    ///
+    /// Reference impedances are read as in [`SpData::from_ascii`].
+    pub fn from_binary(ast: BinaryAst) -> Result<Self> {
+        let values = ast
+            .header
+            .values
+            .iter()
+            .map(|(name, v)| {
+                let value = match v {
+                    BinaryValue::Real(x) => Some(*x),
+                    BinaryValue::Int(x) => Some(*x as f64),
+                    _ => None,
+                };
+                (name.to_string(), value)
+            })
+            .collect::<Vec<_>>();
+        let data = AcData::try_from_binary(ast)?;
+        let z0 = port_impedances(
+            values.iter().map(|(name, value)| (name.as_str(), *value)),
+            count_ports(&data),
+        )?;
+        Self::from_ac(&data, &z0)
+    }
+
+    /// The S-parameter from port `j` to port `i` at each frequency.
+    ///
+    /// Port indices are 1-based, matching signal names such as `s21`.
+    pub fn s(&self, i: usize, j: usize) -> Option<Vec<Complex64>> {
+        if i == 0 || j == 0 || i > self.ports || j > self.ports {
+            return None;
+        }
+        let idx = (i - 1) * self.ports + (j - 1);
+        Some(self.s.iter().map(|m| m[idx]).collect())
+    }
+
+    /// Converts to impedance (Z) parameters.
+    ///
+    /// Uses `Z = sqrt(Z0) (I - S)^-1 (I + S) sqrt(Z0)`.
+    /// Returns `None` if the conversion is singular at any frequency.
+    pub fn z(&self) -> Option<Vec<Vec<Complex64>>> {
+        let r = self.z0.iter().map(|z| z.sqrt()).collect::<Vec<_>>();
+        self.convert(&r, -1.)
+    }
+
+    /// Converts to admittance (Y) parameters.
+    ///
+    /// Uses `Y = sqrt(Y0) (I + S)^-1 (I - S) sqrt(Y0)`.
+    /// Returns `None` if the conversion is singular at any frequency.
+    pub fn y(&self) -> Option<Vec<Vec<Complex64>>> {
+        let r = self.z0.iter().map(|z| 1. / z.sqrt()).collect::<Vec<_>>();
+        self.convert(&r, 1.)
+    }
+
+    /// Computes `diag(r) (I + sign S)^-1 (I - sign S) diag(r)` at each frequency.
+    fn convert(&self, r: &[f64], sign: f64) -> Option<Vec<Vec<Complex64>>> {
+        let n = self.ports;
+        self.s
+            .iter()
+            .map(|s| {
+                let mut a = vec![Complex64::new(0., 0.); n * n];
+                let mut b = a.clone();
+                for i in 0..n {
+                    for j in 0..n {
+                        let id = if i == j { 1. } else { 0. };
+                        a[i * n + j] = id + sign * s[i * n + j];
+                        b[i * n + j] = id - sign * s[i * n + j];
+                    }
+                }
+                let a = invert(n, a)?;
+                let mut out = vec![Complex64::new(0., 0.); n * n];
+                for i in 0..n {
+                    for j in 0..n {
+                        let x = (0..n)
+                            .map(|k| a[i * n + k] * b[k * n + j])
+                            .sum::<Complex64>();
+                        out[i * n + j] = r[i] * x * r[j];
+                    }
+                }
+                Some(out)
+            })
+            .collect()
+    }
+
+    /// The conventional Touchstone file extension for this data, e.g. `s2p`.
+    pub fn touchstone_extension(&self) -> String {
+        format!("s{}p", self.ports)
+    }
+
+    /// Writes the S-parameters in Touchstone (version 1) format.
+    ///
+    /// Touchstone version 1 supports a single reference impedance,
+    /// so all ports must share the same impedance.
+    pub fn write_touchstone(&self, mut w: impl Write, format: TouchstoneFormat) -> Result<()> {
+        let z0 = *self
+            .z0
+            .first()
+            .ok_or_else(|| anyhow!("no S-parameters to write"))?;
+        if self.z0.iter().any(|z| *z != z0) {
+            bail!("Touchstone v1 requires all ports to share a reference impedance");
+        }
+
+        writeln!(w, "! {}-port S-parameters", self.ports)?;
+        writeln!(w, "# Hz S {} R {}", format.option(), z0)?;
+
+        let n = self.ports;
+        for (f, s) in self.freq.iter().zip(self.s.iter()) {
+            let mut line = format!("{f:.12e}");
+            if n == 2 {
+                // Two-port data is written in column-major order: S11 S21 S12 S22.
+                for idx in [0, 2, 1, 3] {
+                    let (a, b) = format.split(s[idx]);
+                    write!(line, " {a:.12e} {b:.12e}")?;
+                }
+            } else {
+                for i in 0..n {
+                    for j in 0..n {
+                        // Each matrix row starts on a new line,
+                        // with at most four entries per line.
+                        if (j > 0 && j % 4 == 0) || (j == 0 && i > 0) {
+                            writeln!(w, "{line}")?;
+                            line = String::new();
+                        }
+                        let (a, b) = format.split(s[i * n + j]);
+                        write!(line, " {a:.12e} {b:.12e}")?;
+                    }
+                }
+            }
+            writeln!(w, "{line}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Inverts an `n` by `n` complex matrix stored in row-major order
+/// using Gauss-Jordan elimination with partial pivoting.
+fn invert(n: usize, mut a: Vec<Complex64>) -> Option<Vec<Complex64>> {
+    let mut inv = vec![Complex64::new(0., 0.); n * n];
+    for i in 0..n {
+        inv[i * n + i] = Complex64::new(1., 0.);
+    }
+
+    for col in 0..n {
+        let pivot =
+            (col..n).max_by(|&x, &y| a[x * n + col].norm().total_cmp(&a[y * n + col].norm()))?;
+        if a[pivot * n + col].norm() == 0. {
+            return None;
+        }
+        for k in 0..n {
+            a.swap(col * n + k, pivot * n + k);
+            inv.swap(col * n + k, pivot * n + k);
+        }
+
+        let p = a[col * n + col];
+        for k in 0..n {
+            a[col * n + k] /= p;
+            inv[col * n + k] /= p;
+        }
+        for row in 0..n {
+            if row != col {
+                let factor = a[row * n + col];
+                for k in 0..n {
+                    let (x, y) = (a[col * n + k], inv[col * n + k]);
+                    a[row * n + k] -= factor * x;
+                    inv[row * n + k] -= factor * y;
+                }
+            }
+        }
+    }
+
+    Some(inv)
+}
@@ -0,0 +1,274 @@
+use std::f64::consts::PI;
+
+use anyhow::{anyhow, bail};
+use num::complex::Complex64;
+use rustfft::FftPlanner;
+
+use crate::analysis::transient::{interp, TransientData};
+use crate::Result;
+
+/// A window function applied to samples before taking the FFT.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub enum Window {
+    /// No windowing.
+    Rectangular,
+    /// The Hann (raised cosine) window.
+    #[default]
+    Hann,
+    /// The 4-term Blackman-Harris window.
+    BlackmanHarris,
+}
+
+impl Window {
+    fn coefficients(self) -> &'static [f64] {
+        match self {
+            Self::Rectangular => &[1.],
+            Self::Hann => &[0.5, 0.5],
+            Self::BlackmanHarris => &[0.35875, 0.48829, 0.14128, 0.01168],
+        }
+    }
+
+    /// The value of the window at sample `k` of `n`.
+    pub fn value(self, k: usize, n: usize) -> f64 {
+        let x = 2. * PI * k as f64 / n as f64;
+        self.coefficients()
+            .iter()
+            .enumerate()
+            .map(|(i, a)| {
+                let sign = if i % 2 == 0 { 1. } else { -1. };
+                sign * a * (i as f64 * x).cos()
+            })
+            .sum()
+    }
+
+    /// The number of bins on either side of a coherently sampled tone
+    /// that receive some of its power.
+    pub fn half_width(self) -> usize {
+        self.coefficients().len() - 1
+    }
+}
+
+/// Parameters for computing the spectrum of a transient signal.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SpectrumParams {
+    /// The frequency of the fundamental tone, in Hz.
+    pub fundamental: f64,
+    /// The number of periods of the fundamental contained in the analysis window.
+    ///
+    /// Should be coprime with `points` for the samples to exercise distinct codes.
+    pub periods: usize,
+    /// The number of points in the FFT.
+    pub points: usize,
+    /// The end of the analysis window.
+    ///
+    /// Defaults to the end of the simulation, so that start-up transients are excluded.
+    pub t_stop: Option<f64>,
+    /// The window function to apply.
+    pub window: Window,
+}
+
+impl SpectrumParams {
+    pub fn new(fundamental: f64, periods: usize, points: usize) -> Self {
+        Self {
+            fundamental,
+            periods,
+            points,
+            t_stop: None,
+            window: Window::default(),
+        }
+    }
+}
+
+/// A single-sided power spectrum.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spectrum {
+    /// The frequency of each bin, in Hz.
+    pub freq: Vec<f64>,
+    /// The power in each bin.
+    ///
+    /// A sinusoid of amplitude `A` has a total power of `A^2 / 2`,
+    /// spread over the bins of its window's main lobe.
+    pub power: Vec<f64>,
+    /// The bin containing the fundamental tone.
+    pub fundamental_bin: usize,
+    /// The window function applied to the samples.
+    pub window: Window,
+}
+
+/// Spectral performance metrics, as computed by [`Spectrum::metrics`].
+///
+/// All ratios are in dB.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SpectralMetrics {
+    /// The power of the fundamental tone.
+    pub signal_power: f64,
+    /// The total power of the considered harmonics.
+    pub harmonic_power: f64,
+    /// The total power of everything other than DC, the fundamental and the harmonics.
+    pub noise_power: f64,
+    /// Total harmonic distortion, relative to the fundamental.
+    pub thd: f64,
+    /// Signal to noise ratio.
+    pub snr: f64,
+    /// Signal to noise and distortion ratio.
+    pub sinad: f64,
+    /// Effective number of bits.
+    pub enob: f64,
+    /// Spurious-free dynamic range.
+    pub sfdr: f64,
+}
+
+impl Spectrum {
+    /// Computes the spectrum of the signal `name`.
+    ///
+    /// The signal is linearly interpolated at `params.points` uniformly spaced times
+    /// spanning exactly `params.periods` periods of the fundamental, so that the
+    /// fundamental and its harmonics fall on FFT bins.
+    ///
+    /// Returns an error if the signal does not exist, if the parameters are invalid,
+    /// or if the simulation does not cover the analysis window.
+    pub fn from_transient(
+        data: &TransientData,
+        name: &str,
+        params: &SpectrumParams,
+    ) -> Result<Self> {
+        let time = data
+            .signal(&data.time)
+            .ok_or_else(|| anyhow!("missing time signal `{}`", data.time))?;
+        let values = data
+            .signal(name)
+            .ok_or_else(|| anyhow!("no signal named `{name}`"))?;
+        let n = params.points;
+        if !(params.fundamental > 0. && params.fundamental.is_finite()) {
+            bail!(
+                "fundamental frequency must be positive, got {}",
+                params.fundamental
+            );
+        }
+        if params.periods == 0 || 2 * params.periods >= n {
+            bail!(
+                "{} periods cannot be resolved with {n} points",
+                params.periods
+            );
+        }
+
+        let (Some(&first), Some(&last)) = (time.first(), time.last()) else {
+            bail!("signal `{name}` is empty");
+        };
+        let t_stop = params.t_stop.unwrap_or(last);
+        let span = params.periods as f64 / params.fundamental;
+        let t_start = t_stop - span;
+        if !span.is_finite() || t_start < first || t_stop > last {
+            bail!("analysis window [{t_start}, {t_stop}] is not covered by the simulation [{first}, {last}]");
+        }
+
+        let dt = span / n as f64;
+        let mut buf = (0..n)
+            .map(|k| {
+                let x = interp(time, values, t_start + k as f64 * dt);
+                Complex64::new(x * params.window.value(k, n), 0.)
+            })
+            .collect::<Vec<_>>();
+        FftPlanner::new().plan_fft_forward(n).process(&mut buf);
+
+        // Normalize such that the powers of all bins sum to the mean square of the samples.
+        let w2 = (0..n)
+            .map(|k| params.window.value(k, n).powi(2))
+            .sum::<f64>();
+        let scale = 1. / (n as f64 * w2);
+        let power = buf[..=n / 2]
+            .iter()
+            .enumerate()
+            .map(|(k, x)| {
+                let p = x.norm_sqr() * scale;
+                if k == 0 || 2 * k == n {
+                    p
+                } else {
+                    2. * p
+                }
+            })
+            .collect();
+        let freq = (0..=n / 2).map(|k| k as f64 / span).collect();
+
+        Ok(Self {
+            freq,
+            power,
+            fundamental_bin: params.periods,
+            window: params.window,
+        })
+    }
+
+    /// The power in each bin, in dB.
+    pub fn power_db(&self) -> Vec<f64> {
+        self.power.iter().map(|p| 10. * p.log10()).collect()
+    }
+
+    /// The number of points in the FFT that produced this spectrum.
+    fn points(&self) -> usize {
+        2 * (self.power.len() - 1)
+    }
+
+    /// The bin that harmonic `order` of the fundamental aliases to.
+    fn harmonic_bin(&self, order: usize) -> usize {
+        let n = self.points();
+        let bin = (order * self.fundamental_bin) % n;
+        bin.min(n - bin)
+    }
+
+    /// Computes spectral metrics, treating harmonics 2 through `harmonics` as distortion.
+    ///
+    /// Each bin is attributed to at most one tone: bins shared by the windows of
+    /// several tones count towards the fundamental, then DC, then the lowest harmonic.
+    pub fn metrics(&self, harmonics: usize) -> SpectralMetrics {
+        let w = self.window.half_width();
+        let mut excluded = vec![false; self.power.len()];
+        // Claims the unclaimed bins around `bin`, returning their total power.
+        let mut claim = |bin: usize| {
+            let lo = bin.saturating_sub(w);
+            let hi = (bin + w).min(excluded.len() - 1);
+            excluded[lo..=hi]
+                .iter_mut()
+                .zip(&self.power[lo..=hi])
+                .filter(|(excluded, _)| !**excluded)
+                .map(|(excluded, p)| {
+                    *excluded = true;
+                    p
+                })
+                .sum::<f64>()
+        };
+
+        let signal_power = claim(self.fundamental_bin);
+        claim(0);
+
+        let mut harmonic_power = 0.;
+        let mut max_spur = 0f64;
+        for order in 2..=harmonics {
+            // Harmonics that alias onto DC, the fundamental or another harmonic
+            // only contribute the bins not already claimed.
+            let p = claim(self.harmonic_bin(order));
+            harmonic_power += p;
+            max_spur = max_spur.max(p);
+        }
+
+        let mut noise_power = 0.;
+        for (p, excluded) in self.power.iter().zip(excluded.iter()) {
+            if !excluded {
+                noise_power += p;
+                max_spur = max_spur.max(*p);
+            }
+        }
+
+        let db = |x: f64| 10. * x.log10();
+        let sinad = db(signal_power / (noise_power + harmonic_power));
+        SpectralMetrics {
+            signal_power,
+            harmonic_power,
+            noise_power,
+            thd: db(harmonic_power / signal_power),
+            snr: db(signal_power / noise_power),
+            sinad,
+            enob: (sinad - 1.76) / 6.02,
+            sfdr: db(signal_power / max_spur),
+        }
+    }
+}
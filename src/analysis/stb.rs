@@ -0,0 +1,152 @@
+use num::complex::Complex64;
+
+use crate::analysis::ac::{
+    gain_margin, phase_crossover_freq, phase_margin, unity_gain_freq, AcData,
+};
+use crate::ascii::ast::{PsfAst as AsciiAst, Value as AsciiValue};
+use crate::binary::ast::{PsfAst as BinaryAst, Value as BinaryValue};
+use crate::Result;
+
+/// The name of the loop gain signal written by Spectre.
+pub const LOOP_GAIN: &str = "loopGain";
+
+/// Results of a Spectre stability (`stb`) analysis.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StbData {
+    /// The loop gain and probe data, swept over frequency.
+    pub data: AcData,
+    /// The margins reported by the simulator, if any.
+    pub reported: StbMargins,
+}
+
+/// Stability margins of a feedback loop.
+///
+/// Margins that could not be determined are `None`.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct StbMargins {
+    /// The phase margin, in degrees.
+    pub phase_margin: Option<f64>,
+    /// The frequency at which the phase margin is measured,
+    /// i.e. the unity gain (crossover) frequency.
+    pub phase_margin_freq: Option<f64>,
+    /// The gain margin, in dB.
+    pub gain_margin: Option<f64>,
+    /// The frequency at which the gain margin is measured.
+    pub gain_margin_freq: Option<f64>,
+}
+
+impl StbMargins {
+    fn from_lookup(lookup: impl Fn(&str) -> Option<f64>) -> Self {
+        Self {
+            phase_margin: lookup("phaseMargin"),
+            phase_margin_freq: lookup("phaseMarginFreq"),
+            gain_margin: lookup("gainMargin"),
+            gain_margin_freq: lookup("gainMarginFreq"),
+        }
+    }
+}
+
+impl StbData {
+    /// Reads stability data from an ASCII PSF.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the file is not a valid stability analysis;
+    /// see [`Self::try_from_ascii`].
+    pub fn from_ascii(ast: &AsciiAst) -> Self {
+        Self::try_from_ascii(ast).unwrap()
+    }
+
+    /// Reads stability data from an ASCII PSF.
+    pub fn try_from_ascii(ast: &AsciiAst) -> Result<Self> {
+        let reported = StbMargins::from_lookup(|name| {
+            ast.header
+                .values
+                .iter()
+                .find(|v| v.name == name)
+                .and_then(|v| match v.value {
+                    AsciiValue::Real(x) => Some(x),
+                    AsciiValue::Int(x) => Some(x as f64),
+                    _ => None,
+                })
+        });
+
+        Ok(Self {
+            data: AcData::try_from_ascii(ast)?,
+            reported,
+        })
+    }
+
+    /// Reads stability data from a binary PSF.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the file is not a valid stability analysis;
+    /// see [`Self::try_from_binary`].
+    pub fn from_binary(ast: BinaryAst) -> Self {
+        Self::try_from_binary(ast).unwrap()
+    }
+
+    /// Reads stability data from a binary PSF.
+    pub fn try_from_binary(ast: BinaryAst) -> Result<Self> {
+        let reported = StbMargins::from_lookup(|name| match ast.header.values.get(name)? {
+            BinaryValue::Real(x) => Some(*x),
+            BinaryValue::Int(x) => Some(*x as f64),
+            _ => None,
+        });
+
+        Ok(Self {
+            data: AcData::try_from_binary(ast)?,
+            reported,
+        })
+    }
+
+    #[inline]
+    pub fn freq(&self) -> &Vec<f64> {
+        &self.data.freq
+    }
+
+    /// The loop gain at each frequency.
+    ///
+    /// Spectre includes the sign of the feedback in the loop gain,
+    /// so a negative feedback loop has a low frequency phase of 180 degrees.
+    #[inline]
+    pub fn loop_gain(&self) -> Option<&Vec<Complex64>> {
+        self.data.signal(LOOP_GAIN)
+    }
+
+    /// The loop gain with the feedback sign removed,
+    /// such that the closed loop response is `T / (1 + T)`.
+    fn return_ratio(&self) -> Option<Vec<Complex64>> {
+        Some(self.loop_gain()?.iter().map(|x| -x).collect())
+    }
+
+    /// The frequency at which the magnitude of the loop gain crosses unity.
+    pub fn crossover_freq(&self) -> Option<f64> {
+        unity_gain_freq(self.freq(), self.loop_gain()?)
+    }
+
+    /// The phase margin of the loop, in degrees.
+    pub fn phase_margin(&self) -> Option<f64> {
+        phase_margin(self.freq(), &self.return_ratio()?)
+    }
+
+    /// The gain margin of the loop, in dB.
+    pub fn gain_margin(&self) -> Option<f64> {
+        gain_margin(self.freq(), &self.return_ratio()?)
+    }
+
+    /// Computes the stability margins from the simulated loop gain.
+    ///
+    /// The result can be compared against the margins [`reported`](Self::reported)
+    /// by the simulator.
+    pub fn margins(&self) -> StbMargins {
+        let t = self.return_ratio();
+        StbMargins {
+            phase_margin: self.phase_margin(),
+            phase_margin_freq: self.crossover_freq(),
+            gain_margin: self.gain_margin(),
+            gain_margin_freq: t.and_then(|t| phase_crossover_freq(self.freq(), &t)),
+        }
+    }
+}
@@ -0,0 +1,1120 @@
+use std::collections::HashMap;
+
+use float_eq::assert_float_eq;
+use num::complex::Complex64;
+
+use crate::analysis::ac::{AcData, Probe};
+use crate::analysis::digital::{level_at, Edge, Encoding, Logic, Threshold};
+use crate::analysis::montecarlo::{MonteCarlo, Worst, MC_SWEEP};
+use crate::analysis::noise::{NoiseData, NoiseKind};
+use crate::analysis::pac::{PacData, PnoiseData};
+use crate::analysis::pss::PssFreqData;
+use crate::analysis::sp::{SpData, TouchstoneFormat};
+#[cfg(feature = "fft")]
+use crate::analysis::spectrum::{Spectrum, SpectrumParams, Window};
+use crate::analysis::stb::StbData;
+use crate::analysis::transient::{Grid, Interp, TransientData};
+use crate::analysis::xf::XfData;
+use crate::ascii::ast::Values;
+use crate::ascii::parse as ascii_parse;
+use crate::ascii::tests::{AC_EXAMPLE_PSF, VDIV_SIN_PSF};
+use crate::binary::ast::{
+    Properties, PsfAst as BinaryAst, SignalRef, Trace as BinaryTrace, TraceId, TypeId,
+    Value as BinaryValue, Values as BinaryValues,
+};
+use crate::binary::parse as binary_parse;
+use crate::binary::tests::VDIV_SIN_PSFBIN;
+
+fn ramp() -> TransientData {
+    // Non-uniform time steps, as produced by an adaptive timestep simulator.
+    let time = vec![0., 1., 3., 4.];
+    let x = vec![0., 1., 3., 4.];
+    TransientData {
+        signals: HashMap::from([("time".to_string(), time), ("x".to_string(), x)]),
+        time: "time".to_string(),
+        units: HashMap::new(),
+    }
+}
+
+#[test]
+fn stats_ramp() {
+    let data = ramp();
+    let stats = data.stats("x", 0., 4.).unwrap();
+    assert_float_eq!(stats.min, 0., abs <= 1e-12);
+    assert_float_eq!(stats.max, 4., abs <= 1e-12);
+    assert_float_eq!(stats.max_time, 4., abs <= 1e-12);
+    assert_float_eq!(stats.integral, 8., abs <= 1e-12);
+    assert_float_eq!(stats.mean, 2., abs <= 1e-12);
+    assert_float_eq!(stats.rms, (16f64 / 3.).sqrt(), abs <= 1e-12);
+    assert_float_eq!(stats.std_dev, (4f64 / 3.).sqrt(), abs <= 1e-12);
+
+    let stats = data.stats("x", 0.5, 2.).unwrap();
+    assert_float_eq!(stats.min, 0.5, abs <= 1e-12);
+    assert_float_eq!(stats.min_time, 0.5, abs <= 1e-12);
+    assert_float_eq!(stats.max, 2., abs <= 1e-12);
+    assert_float_eq!(stats.integral, 1.875, abs <= 1e-12);
+    assert_float_eq!(stats.mean, 1.25, abs <= 1e-12);
+
+    assert!(data.stats("y", 0., 4.).is_none());
+    assert!(data.stats("x", 5., 6.).is_none());
+}
+
+#[test]
+fn stats_vdiv_sin() {
+    let ast = ascii_parse(VDIV_SIN_PSF).expect("Failed to parse transient PSF file");
+    let data = TransientData::from_ascii(&ast);
+
+    // vin = 1 + 3 sin(2 pi 100MHz t)
+    let stats = data.stats("vin", 0., 1e-6).unwrap();
+    assert_float_eq!(stats.mean, 1., abs <= 1e-3);
+    assert_float_eq!(stats.integral, 1e-6, rmax <= 1e-3);
+    // The signal is only sampled 10 times per period, so the piecewise linear
+    // reconstruction slightly underestimates the RMS value.
+    assert_float_eq!(stats.rms, 5.5f64.sqrt(), rmax <= 5e-2);
+    assert_float_eq!(stats.max, 3.853, abs <= 1e-3);
+    assert_float_eq!(stats.min, -1.853, abs <= 1e-3);
+}
+
+#[test]
+fn resample_ramp() {
+    let data = ramp();
+
+    let resampled = data.resample(Grid::Points(9), Interp::Linear).unwrap();
+    let time = resampled.signal("time").unwrap();
+    assert_eq!(time.len(), 9);
+    assert_float_eq!(time[1], 0.5, abs <= 1e-12);
+    assert_float_eq!(resampled.signal("x").unwrap()[5], 2.5, abs <= 1e-12);
+
+    let resampled = data.resample(Grid::Step(1.), Interp::Previous).unwrap();
+    assert_eq!(resampled.signal("x").unwrap(), &vec![0., 1., 1., 3., 4.]);
+
+    let resampled = data
+        .resample_signals(&["x"], Grid::Step(2.), Interp::Average)
+        .unwrap();
+    assert_eq!(resampled.signals.len(), 2);
+    assert_eq!(resampled.signal("x").unwrap(), &vec![0.5, 2., 3.5]);
+
+    assert!(data.resample(Grid::Step(0.), Interp::Linear).is_err());
+    assert!(data
+        .resample_signals(&["y"], Grid::Points(4), Interp::Linear)
+        .is_err());
+
+    let mut short = data.clone();
+    short.signals.get_mut("x").unwrap().pop();
+    for interp in [Interp::Previous, Interp::Linear, Interp::Average] {
+        assert!(short.resample(Grid::Points(4), interp).is_err());
+    }
+    short.signals.insert("x".to_string(), Vec::new());
+    assert!(short.resample(Grid::Points(4), Interp::Linear).is_err());
+}
+
+#[test]
+fn resample_vdiv_sin() {
+    let ast = ascii_parse(VDIV_SIN_PSF).expect("Failed to parse transient PSF file");
+    let data = TransientData::from_ascii(&ast);
+
+    let resampled = data.resample(Grid::Step(0.5e-9), Interp::Linear).unwrap();
+    assert_eq!(resampled.signals.len(), 4);
+    assert_eq!(resampled.signal("time").unwrap().len(), 32001);
+
+    // Averaging over a full period removes the sinusoid entirely.
+    let resampled = data
+        .resample_signals(&["vin"], Grid::Step(10e-9), Interp::Average)
+        .unwrap();
+    let vin = resampled.signal("vin").unwrap();
+    for &v in &vin[1..vin.len() - 1] {
+        assert_float_eq!(v, 1., abs <= 1e-6);
+    }
+}
+
+#[test]
+fn concat_restarted_segments() {
+    let segment = |time: Vec<f64>, offset: f64| {
+        let x = time.iter().map(|t| t + offset).collect();
+        TransientData {
+            signals: HashMap::from([("time".to_string(), time), ("x".to_string(), x)]),
+            time: "time".to_string(),
+            units: HashMap::from([("x".to_string(), "V".to_string())]),
+        }
+    };
+    let first = segment(vec![0., 1., 2., 3.], 0.);
+    // Restarted from a state saved at t = 2.
+    let second = segment(vec![2., 2.5, 4.], 10.);
+
+    let data = TransientData::concat(&[first.clone(), second.clone()]).unwrap();
+    assert_eq!(data.signal("time").unwrap(), &vec![0., 1., 2., 2.5, 4.]);
+    assert_eq!(data.signal("x").unwrap(), &vec![0., 1., 12., 12.5, 14.]);
+    assert_eq!(data.units["x"], "V");
+
+    let mut other_units = second.clone();
+    other_units.units.insert("x".to_string(), "A".to_string());
+    assert!(TransientData::concat(&[first.clone(), other_units]).is_err());
+
+    let mut other_signals = second;
+    other_signals.signals.insert("y".to_string(), vec![0.; 3]);
+    assert!(TransientData::concat(&[first, other_signals]).is_err());
+    assert!(TransientData::concat(&[]).is_err());
+}
+
+#[test]
+fn merge_signal_subsets() {
+    let ast = ascii_parse(VDIV_SIN_PSF).unwrap();
+    let full = TransientData::from_ascii(&ast);
+    let time = full.signal("time").unwrap();
+    let subset = |name: &str| {
+        full.resample_signals(&[name], Grid::Points(time.len()), Interp::Linear)
+            .unwrap()
+    };
+    let names = full
+        .signals
+        .keys()
+        .filter(|name| **name != full.time)
+        .collect::<Vec<_>>();
+    assert!(names.len() > 1);
+
+    let parts = names.iter().map(|name| subset(name)).collect::<Vec<_>>();
+    let merged = TransientData::merge_signals(&parts).unwrap();
+    assert_eq!(merged.signals.len(), full.signals.len());
+    assert_eq!(merged.units, full.units);
+    assert_eq!(full.units["time"], "s");
+    let binary = TransientData::from_binary(binary_parse(VDIV_SIN_PSFBIN).unwrap());
+    assert_eq!(binary.units, full.units);
+
+    let mut shifted = parts[1].clone();
+    shifted.signals.get_mut("time").unwrap()[1] += 1e-3;
+    assert!(TransientData::merge_signals(&[parts[0].clone(), shifted]).is_err());
+}
+
+#[test]
+fn digitize_thresholds() {
+    let time = [0., 1., 2., 3., 4.];
+    let values = [0., 1., 0.5, 1., 0.];
+    let check = |threshold: Threshold, expected: &[(f64, Logic)]| {
+        let events = threshold.digitize(&time, &values);
+        assert_eq!(events.len(), expected.len());
+        for ((t, level), (te, le)) in events.iter().zip(expected) {
+            assert_float_eq!(*t, *te, abs <= 1e-12);
+            assert_eq!(level, le);
+        }
+    };
+
+    let threshold = Threshold::cmos(1.).unwrap();
+    check(
+        threshold,
+        &[
+            (0., Logic::Low),
+            (0.3, Logic::X),
+            (0.7, Logic::High),
+            (1.6, Logic::X),
+            (2.4, Logic::High),
+            (3.3, Logic::X),
+            (3.7, Logic::Low),
+        ],
+    );
+    check(
+        threshold.with_hysteresis(),
+        &[(0., Logic::Low), (0.7, Logic::High), (3.7, Logic::Low)],
+    );
+    check(
+        Threshold::new(0.5, 0.5).unwrap(),
+        &[(0., Logic::Low), (0.5, Logic::High), (3.5, Logic::Low)],
+    );
+    assert!(Threshold::new(0.6, 0.4).is_err());
+    assert!(Threshold::new(f64::NAN, 0.4).is_err());
+    assert!(Threshold::cmos(-1.).is_err());
+
+    let events = threshold.with_hysteresis().digitize(&time, &values);
+    assert_eq!(level_at(&events, -1.), None);
+    assert_eq!(level_at(&events, 2.), Some(Logic::High));
+    assert_eq!(level_at(&events, 4.), Some(Logic::Low));
+}
+
+#[test]
+fn bus_codes() {
+    // Codes 0b101, 0b011, 0b110 and 0b000, each held across one clock rising edge.
+    let time = (0..9).map(f64::from).collect::<Vec<_>>();
+    let clk = vec![0., 1., 0., 1., 0., 1., 0., 1., 0.];
+    let q0 = vec![1., 1., 1., 1., 0., 0., 0., 0., 0.];
+    let q1 = vec![0., 0., 1., 1., 1., 1., 0., 0., 0.];
+    let q2 = vec![1., 1., 0., 0., 1., 1., 0., 0., 0.];
+    let mut data = TransientData {
+        signals: HashMap::from([
+            ("time".to_string(), time),
+            ("v(clk)".to_string(), clk),
+            ("v(q[0])".to_string(), q0),
+            ("v(q[1])".to_string(), q1),
+            ("v(q[2])".to_string(), q2.clone()),
+            ("v(qb[0])".to_string(), q2),
+        ]),
+        time: "time".to_string(),
+        units: HashMap::new(),
+    };
+    let threshold = Threshold::cmos(1.).unwrap();
+
+    let rising = data.edges("v(clk)", threshold, Edge::Rising).unwrap();
+    assert_eq!(rising.len(), 4);
+    for (t, expected) in rising.iter().zip([0.7, 2.7, 4.7, 6.7]) {
+        assert_float_eq!(*t, expected, abs <= 1e-12);
+    }
+    let falling = data.edges("v(clk)", threshold, Edge::Falling).unwrap();
+    assert_eq!(falling.len(), 4);
+    assert_float_eq!(falling[0], 1.7, abs <= 1e-12);
+    assert_eq!(
+        data.edges("v(clk)", threshold, Edge::Both).unwrap().len(),
+        8
+    );
+    assert!(data.edges("v(missing)", threshold, Edge::Rising).is_none());
+
+    let bus = data.bus("q", threshold).unwrap();
+    assert_eq!(bus.width(), 3);
+    assert_eq!(bus.signals, ["v(q[0])", "v(q[1])", "v(q[2])"]);
+    let codes = |encoding| {
+        bus.codes(&rising, encoding)
+            .into_iter()
+            .map(Option::unwrap)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(codes(Encoding::Unsigned), [5, 3, 6, 0]);
+    assert_eq!(codes(Encoding::Signed), [-3, 3, -2, 0]);
+    assert_eq!(codes(Encoding::Gray), [6, 2, 4, 0]);
+    // Bits 1 and 2 are both switching at t = 1.5.
+    assert_eq!(bus.code(1.5, Encoding::Unsigned), None);
+    assert_eq!(bus.code(-1., Encoding::Unsigned), None);
+
+    assert!(data.bus("missing", threshold).is_err());
+    data.signals.remove("v(q[1])");
+    assert!(data.bus("q", threshold).is_err());
+}
+
+#[cfg(feature = "fft")]
+fn distorted_sine(amplitude: f64, distortion: f64) -> TransientData {
+    let f0 = 1e6;
+    let n = 20_000;
+    let time = (0..=n).map(|i| i as f64 * 1e-9).collect::<Vec<_>>();
+    let x = time
+        .iter()
+        .map(|t| {
+            let phase = 2. * std::f64::consts::PI * f0 * t;
+            0.5 + amplitude * phase.sin() + distortion * (3. * phase).cos()
+        })
+        .collect();
+    TransientData {
+        signals: HashMap::from([("time".to_string(), time), ("x".to_string(), x)]),
+        time: "time".to_string(),
+        units: HashMap::new(),
+    }
+}
+
+#[cfg(feature = "fft")]
+#[test]
+fn spectrum_harmonic_distortion() {
+    let data = distorted_sine(1., 0.01);
+    for window in [Window::Rectangular, Window::Hann, Window::BlackmanHarris] {
+        let mut params = SpectrumParams::new(1e6, 7, 256);
+        params.window = window;
+        let spectrum = Spectrum::from_transient(&data, "x", &params).unwrap();
+        assert_eq!(spectrum.power.len(), 129);
+        assert_float_eq!(spectrum.freq[7], 1e6, rmax <= 1e-12);
+
+        let metrics = spectrum.metrics(5);
+        assert_float_eq!(metrics.signal_power, 0.5, rmax <= 1e-3);
+        assert_float_eq!(metrics.thd, -40., abs <= 0.1);
+        assert_float_eq!(metrics.sfdr, 40., abs <= 0.1);
+        assert!(metrics.snr > 60.);
+        assert_float_eq!(metrics.sinad, 40., abs <= 0.1);
+    }
+
+    let spectrum = Spectrum::from_transient(&data, "x", &SpectrumParams::new(1e6, 7, 256)).unwrap();
+    // With no harmonics considered, the third harmonic counts as noise.
+    let metrics = spectrum.metrics(1);
+    assert_float_eq!(metrics.snr, 40., abs <= 0.1);
+    assert_float_eq!(metrics.enob, (40. - 1.76) / 6.02, abs <= 0.1);
+
+    // With the fundamental in bin 2, the windows of the fundamental and its
+    // harmonics overlap, but all of their power is still accounted for.
+    let mut data = distorted_sine(1., 0.01);
+    data.signals
+        .get_mut("x")
+        .unwrap()
+        .iter_mut()
+        .for_each(|x| *x -= 0.5);
+    let spectrum = Spectrum::from_transient(&data, "x", &SpectrumParams::new(1e6, 2, 256)).unwrap();
+    let metrics = spectrum.metrics(5);
+    assert_float_eq!(metrics.thd, -40., abs <= 0.1);
+    assert_float_eq!(metrics.sinad, 40., abs <= 0.1);
+    assert_float_eq!(
+        metrics.signal_power + metrics.harmonic_power + metrics.noise_power + spectrum.power[0],
+        spectrum.power.iter().sum::<f64>(),
+        rmax <= 1e-12
+    );
+
+    let params = SpectrumParams::new(1e3, 7, 256);
+    assert!(Spectrum::from_transient(&data, "x", &params).is_err());
+    for fundamental in [0., -1e6, f64::NAN] {
+        let params = SpectrumParams::new(fundamental, 7, 256);
+        assert!(Spectrum::from_transient(&data, "x", &params).is_err());
+    }
+    let params = SpectrumParams::new(1e6, 7, 256);
+    assert!(Spectrum::from_transient(&data, "missing", &params).is_err());
+}
+
+fn first_order_lowpass(f_pole: f64, gain: f64, delay: f64) -> AcData {
+    let freq = (0..=60)
+        .map(|i| 10f64.powf(i as f64 / 10.))
+        .collect::<Vec<_>>();
+    let out = freq
+        .iter()
+        .map(|f| {
+            let s = Complex64::new(0., 2. * std::f64::consts::PI * f);
+            gain / (1. + s / (2. * std::f64::consts::PI * f_pole)) * (-s * delay).exp()
+        })
+        .collect();
+    AcData {
+        signals: HashMap::from([
+            ("out".to_string(), out),
+            ("in".to_string(), vec![Complex64::new(1., 0.); freq.len()]),
+            ("gnd".to_string(), vec![Complex64::new(0., 0.); freq.len()]),
+        ]),
+        freq,
+        units: HashMap::new(),
+    }
+}
+
+#[test]
+fn ac_helpers() {
+    let ast = ascii_parse(AC_EXAMPLE_PSF).expect("Failed to parse ac PSF file");
+    let data = AcData::from_ascii(&ast);
+    let db = data.db20(Probe::Single("Xdut.out")).unwrap();
+    assert_float_eq!(db[0], 20. * (1f64 / 3.).log10(), abs <= 1e-4);
+    let h = data.ratio("Xdut.out", "Xdut.vdd").unwrap();
+    assert_float_eq!(h[0].re, 1. / 3., abs <= 1e-5);
+    let h = data.ratio(("Xdut.vdd", "Xdut.out"), "Xdut.vdd").unwrap();
+    assert_float_eq!(h[0].re, 2. / 3., abs <= 1e-5);
+    assert!(data.mag("missing").is_none());
+
+    let data = first_order_lowpass(1e3, 10., 1e-6);
+    let mag = data.mag("out").unwrap();
+    assert_float_eq!(mag[0], 10., rmax <= 1e-6);
+    assert_float_eq!(data.db10("out").unwrap()[0], 10., abs <= 1e-5);
+    assert_float_eq!(
+        data.db20("out").unwrap()[30],
+        20. - 10. * 2f64.log10(),
+        abs <= 1e-9
+    );
+
+    let phase = data.phase_deg(("out", "gnd")).unwrap();
+    assert!(phase.iter().all(|p| (-180. ..=180.).contains(p)));
+    let unwrapped = data.unwrapped_phase_deg("out").unwrap();
+    assert_float_eq!(unwrapped[30], -45. - 360. * 1e3 * 1e-6, abs <= 1e-9);
+    // The pure delay dominates at high frequencies.
+    assert!(unwrapped[60] < -360.);
+
+    // At low frequencies, the group delay is the sum of the
+    // pure delay and the pole's time constant.
+    let delay = data.group_delay("out").unwrap();
+    let tau = 1. / (2. * std::f64::consts::PI * 1e3);
+    assert_float_eq!(delay[1], 1e-6 + tau, rmax <= 1e-2);
+}
+
+#[test]
+fn ac_measurements() {
+    let data = first_order_lowpass(1e3, 10., 1e-6);
+    assert_float_eq!(data.f_3db("out", "in").unwrap(), 1e3, rmax <= 1e-2);
+
+    let fu = 1e3 * 99f64.sqrt();
+    assert_float_eq!(data.unity_gain_freq("out").unwrap(), fu, rmax <= 1e-2);
+    let pm = 180. - (fu / 1e3).atan().to_degrees() - 360. * fu * 1e-6;
+    assert_float_eq!(data.phase_margin("out").unwrap(), pm, abs <= 0.5);
+
+    // Find where the phase reaches -180 degrees by bisection.
+    let phase = |f: f64| -(f / 1e3).atan().to_degrees() - 360. * f * 1e-6;
+    let (mut lo, mut hi) = (1e3f64, 1e6f64);
+    for _ in 0..100 {
+        let mid = (lo * hi).sqrt();
+        if phase(mid) > -180. {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    let gm = -20. * (10. / (1. + (lo / 1e3).powi(2)).sqrt()).log10();
+    assert_float_eq!(data.gain_margin("out").unwrap(), gm, abs <= 0.1);
+
+    // A first order response peaks at DC.
+    assert_float_eq!(data.peak_gain("out").unwrap(), 20., abs <= 1e-3);
+    assert_float_eq!(data.resonant_freq("out").unwrap(), 1., rmax <= 1e-12);
+
+    // A DC point is skipped when interpolating in log-frequency.
+    let mut dc = first_order_lowpass(1e3, 10., 1e-6);
+    dc.freq.insert(0, 0.);
+    for values in dc.signals.values_mut() {
+        let first = values[0];
+        values.insert(0, first);
+    }
+    assert_eq!(dc.f_3db("out", "in"), data.f_3db("out", "in"));
+    assert_eq!(dc.unity_gain_freq("out"), data.unity_gain_freq("out"));
+    assert_eq!(dc.phase_margin("out"), data.phase_margin("out"));
+    assert_eq!(dc.gain_margin("out"), data.gain_margin("out"));
+    assert_eq!(dc.peak_gain("out"), data.peak_gain("out"));
+
+    // Second order resonance with Q = 5 at 1 kHz.
+    let (f0, q) = (1e3, 5.);
+    let freq = (0..=400)
+        .map(|i| 10f64.powf(1. + i as f64 / 100.))
+        .collect::<Vec<_>>();
+    let out = freq
+        .iter()
+        .map(|f| {
+            let s = Complex64::new(0., f / f0);
+            1. / (1. + s / q + s * s)
+        })
+        .collect();
+    let data = AcData {
+        signals: HashMap::from([("out".to_string(), out)]),
+        freq,
+        units: HashMap::new(),
+    };
+    let peak = 20. * (q / (1. - 1. / (4. * q * q)).sqrt()).log10();
+    assert_float_eq!(data.peak_gain("out").unwrap(), peak, abs <= 1e-3);
+    let fr = f0 * (1. - 1. / (2. * q * q)).sqrt();
+    assert_float_eq!(data.resonant_freq("out").unwrap(), fr, rmax <= 1e-3);
+    assert!(data.gain_margin("out").is_none());
+}
+
+/// Generates an ASCII stb PSF for a two-pole loop with a DC gain of 1000,
+/// using Spectre's sign convention for the loop gain.
+fn stb_psf() -> String {
+    let mut psf = String::from(
+        r#"HEADER
+"PSFversion" "1.00"
+"analysis type" "stb"
+"phaseMargin" 5.180000e+01
+"phaseMarginFreq" 7.860000e+05
+"gainMargin" 1.000000e+02
+TYPE
+"sweep" FLOAT DOUBLE PROP(
+"key" "sweep"
+)
+"V/V" COMPLEX DOUBLE PROP(
+"key" "node"
+)
+SWEEP
+"freq" "sweep" PROP(
+"units" "Hz"
+)
+TRACE
+"loopGain" "V/V"
+VALUE
+"#,
+    );
+    for i in 0..=80 {
+        let f = 10f64.powf(i as f64 / 10.);
+        let s = Complex64::new(0., f);
+        let t = -1e3 / ((1. + s / 1e3) * (1. + s / 1e6));
+        psf.push_str(&format!(
+            "\"freq\" {:.15e}\n\"loopGain\" ({:.15e} {:.15e})\n",
+            f, t.re, t.im
+        ));
+    }
+    psf.push_str("END\n");
+    psf
+}
+
+#[test]
+fn stb_margins() {
+    let psf = stb_psf();
+    let ast = ascii_parse(&psf).expect("Failed to parse stb PSF file");
+    let data = StbData::from_ascii(&ast);
+    assert_eq!(data.freq().len(), 81);
+    assert_float_eq!(data.loop_gain().unwrap()[0].re, -1e3, rmax <= 1e-3);
+
+    assert_eq!(data.reported.phase_margin, Some(51.8));
+    assert_eq!(data.reported.phase_margin_freq, Some(7.86e5));
+    assert_eq!(data.reported.gain_margin, Some(100.));
+    assert_eq!(data.reported.gain_margin_freq, None);
+
+    let margins = data.margins();
+    assert_float_eq!(margins.phase_margin_freq.unwrap(), 7.86e5, rmax <= 2e-2);
+    assert_float_eq!(margins.phase_margin.unwrap(), 51.8, abs <= 1.);
+    // A two-pole loop never reaches -180 degrees.
+    assert_eq!(margins.gain_margin, None);
+    assert_eq!(margins.gain_margin_freq, None);
+}
+
+/// Generates an ASCII noise PSF with a struct-typed MOSFET contribution
+/// and a resistor contribution saved as a plain trace.
+fn noise_psf() -> String {
+    let mut psf = String::from(
+        r#"HEADER
+"PSFversion" "1.00"
+"analysis type" "noise"
+TYPE
+"sweep" FLOAT DOUBLE PROP(
+"key" "sweep"
+)
+"V/sqrt(Hz)" FLOAT DOUBLE PROP(
+"units" "V/sqrt(Hz)"
+)
+"V**2/Hz" FLOAT DOUBLE PROP(
+"units" "V**2/Hz"
+)
+"bsim4_noise" STRUCT(
+"rd" FLOAT DOUBLE
+"id" FLOAT DOUBLE
+"fn" FLOAT DOUBLE
+"total" FLOAT DOUBLE
+) PROP(
+"key" "noise"
+)
+SWEEP
+"freq" "sweep" PROP(
+"units" "Hz"
+)
+TRACE
+"out" "V/sqrt(Hz)"
+"in" "V/sqrt(Hz)"
+"M1" "bsim4_noise"
+"R1" "V**2/Hz"
+VALUE
+"#,
+    );
+    for i in 1..=100 {
+        let f = 1e3 * i as f64;
+        let (rd, id, flicker, r) = (1e-18, 4e-17, 1e-13 / f, 2e-17);
+        let total = rd + id + flicker;
+        psf.push_str(&format!(
+            "\"freq\" {f:.6e}\n\"out\" {:.6e}\n\"in\" {:.6e}\n\
+             \"M1\" ({rd:.6e} {id:.6e} {flicker:.6e} {total:.6e})\n\"R1\" {r:.6e}\n",
+            (total + r).sqrt(),
+            1e-9
+        ));
+    }
+    psf.push_str("END\n");
+    psf
+}
+
+#[test]
+fn noise_contributors() {
+    let psf = noise_psf();
+    let ast = ascii_parse(&psf).expect("Failed to parse noise PSF file");
+    let data = NoiseData::from_ascii(&ast);
+    assert_eq!(data.freq.len(), 100);
+    assert_eq!(data.contributions.len(), 2);
+    assert_eq!(data.contributions["M1"].len(), 4);
+
+    assert_float_eq!(
+        data.integrate_input(1e4, 5e4).unwrap(),
+        1e-9 * 4e4f64.sqrt(),
+        rmax <= 1e-9
+    );
+
+    // Over a wide band, the flicker noise is small compared to
+    // the channel thermal noise.
+    let contributors = data.contributors(1e3, 1e5);
+    assert_eq!(contributors.len(), 4);
+    assert_eq!(contributors[0].device, "M1");
+    assert_eq!(contributors[0].source, "id");
+    assert_eq!(contributors[0].kind, NoiseKind::Thermal);
+    assert_float_eq!(contributors[0].power, 4e-17 * 99e3, rmax <= 1e-6);
+    assert_eq!(contributors[1].device, "R1");
+    assert_eq!(contributors[1].source, "total");
+    assert_eq!(contributors[2].kind, NoiseKind::Flicker);
+    assert_eq!(contributors[3].kind, NoiseKind::Thermal);
+
+    let total = contributors.iter().map(|c| c.power).sum::<f64>();
+    assert_float_eq!(data.integrate(1e3, 1e5), total.sqrt(), rmax <= 1e-5);
+
+    let devices = data.device_totals(1e3, 1e5);
+    assert_eq!(devices[0].0, "M1");
+    assert_eq!(devices[1].0, "R1");
+
+    let kinds = data.kind_totals(1e3, 1e5);
+    assert_eq!(kinds[0].0, NoiseKind::Thermal);
+    assert_eq!(kinds[1].0, NoiseKind::Other);
+    assert_eq!(kinds[2].0, NoiseKind::Flicker);
+}
+
+#[test]
+fn noise_two_field_struct() {
+    // A struct with two fields has the same shape as a complex number,
+    // so its declared type must be used to interpret it.
+    let psf = r#"HEADER
+"PSFversion" "1.00"
+"analysis type" "noise"
+TYPE
+"sweep" FLOAT DOUBLE PROP(
+"key" "sweep"
+)
+"V/sqrt(Hz)" FLOAT DOUBLE PROP(
+"units" "V/sqrt(Hz)"
+)
+"resistor_noise" STRUCT(
+"rn" FLOAT DOUBLE
+"total" FLOAT DOUBLE
+) PROP(
+"key" "noise"
+)
+SWEEP
+"freq" "sweep" PROP(
+"units" "Hz"
+)
+TRACE
+"out" "V/sqrt(Hz)"
+"R1" "resistor_noise"
+VALUE
+"freq" 1.000000e+03
+"out" 2.000000e-09
+"R1" (4.000000e-18 4.000000e-18)
+"freq" 2.000000e+03
+"out" 2.000000e-09
+"R1" (4.000000e-18 4.000000e-18)
+END
+"#;
+    let ast = ascii_parse(psf).expect("Failed to parse noise PSF file");
+    assert_eq!(
+        ast.values[2].values,
+        Values::Struct(vec![vec![4e-18, 4e-18]])
+    );
+
+    let data = NoiseData::from_ascii(&ast);
+    assert_eq!(data.freq, vec![1e3, 2e3]);
+    assert_eq!(data.contributions["R1"]["rn"], vec![4e-18, 4e-18]);
+    assert_eq!(data.contributions["R1"]["total"], vec![4e-18, 4e-18]);
+
+    let contributors = data.contributors(1e3, 2e3);
+    assert_eq!(contributors.len(), 1);
+    assert_eq!(contributors[0].source, "rn");
+    assert_eq!(contributors[0].kind, NoiseKind::Thermal);
+}
+
+#[test]
+fn noise_missing_output() {
+    let psf = noise_psf()
+        .lines()
+        .filter(|line| !line.starts_with("\"out\" "))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let ast = ascii_parse(&psf).expect("Failed to parse noise PSF file");
+    let err = NoiseData::try_from_ascii(&ast).unwrap_err();
+    assert_eq!(err.to_string(), "missing output noise signal `out`");
+}
+
+/// Generates an ASCII sp PSF for a resistive T network with the given Z-matrix.
+fn sp_psf(z: [[f64; 2]; 2], z0: f64) -> String {
+    // S = (Z - Z0) (Z + Z0)^-1 for a two-port with equal real reference impedances.
+    let (a, b, c, d) = (z[0][0] - z0, z[0][1], z[1][0], z[1][1] - z0);
+    let (e, f, g, h) = (z[0][0] + z0, z[0][1], z[1][0], z[1][1] + z0);
+    let det = e * h - f * g;
+    let (ie, i_f, ig, ih) = (h / det, -f / det, -g / det, e / det);
+    let s = [
+        [a * ie + b * ig, a * i_f + b * ih],
+        [c * ie + d * ig, c * i_f + d * ih],
+    ];
+
+    let mut psf = format!(
+        r#"HEADER
+"PSFversion" "1.00"
+"analysis type" "sp"
+"z0" {z0:.6e}
+TYPE
+"sweep" FLOAT DOUBLE PROP(
+"key" "sweep"
+)
+"sparam" COMPLEX DOUBLE
+SWEEP
+"freq" "sweep" PROP(
+"units" "Hz"
+)
+TRACE
+"s11" "sparam"
+"s12" "sparam"
+"s21" "sparam"
+"s22" "sparam"
+VALUE
+"#
+    );
+    for f in [1e6, 1e9] {
+        psf.push_str(&format!("\"freq\" {f:.6e}\n"));
+        for (i, j) in [(0, 0), (0, 1), (1, 0), (1, 1)] {
+            psf.push_str(&format!(
+                "\"s{}{}\" ({:.15e} 0.000000e+00)\n",
+                i + 1,
+                j + 1,
+                s[i][j]
+            ));
+        }
+    }
+    psf.push_str("END\n");
+    psf
+}
+
+#[test]
+fn sp_conversions() {
+    let z = [[110., 100.], [100., 130.]];
+    let psf = sp_psf(z, 75.);
+    let ast = ascii_parse(&psf).expect("Failed to parse sp PSF file");
+    let data = SpData::from_ascii(&ast).unwrap();
+    assert_eq!(data.ports, 2);
+    assert_eq!(data.z0, vec![75., 75.]);
+    assert_eq!(data.s(2, 1).unwrap().len(), 2);
+    assert!(data.s(3, 1).is_none());
+
+    let zp = data.z().unwrap();
+    for (idx, expected) in [z[0][0], z[0][1], z[1][0], z[1][1]].into_iter().enumerate() {
+        assert_float_eq!(zp[1][idx].re, expected, rmax <= 1e-9);
+        assert_float_eq!(zp[1][idx].im, 0., abs <= 1e-9);
+    }
+
+    let yp = data.y().unwrap();
+    let det = z[0][0] * z[1][1] - z[0][1] * z[1][0];
+    assert_float_eq!(yp[0][0].re, z[1][1] / det, rmax <= 1e-9);
+    assert_float_eq!(yp[0][1].re, -z[0][1] / det, rmax <= 1e-9);
+
+    let mut out = Vec::new();
+    data.write_touchstone(&mut out, TouchstoneFormat::RealImag)
+        .unwrap();
+    let out = String::from_utf8(out).unwrap();
+    let lines = out.lines().collect::<Vec<_>>();
+    assert_eq!(data.touchstone_extension(), "s2p");
+    assert_eq!(lines[1], "# Hz S RI R 75");
+    assert_eq!(lines.len(), 4);
+    let fields = lines[2]
+        .split_whitespace()
+        .map(|x| x.parse::<f64>().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(fields.len(), 9);
+    assert_float_eq!(fields[0], 1e6, rmax <= 1e-12);
+    // Two-port data is column-major, so S21 comes second.
+    assert_float_eq!(fields[3], data.s(2, 1).unwrap()[0].re, rmax <= 1e-9);
+}
+
+#[test]
+fn sp_port_impedances() {
+    let psf = sp_psf([[110., 100.], [100., 130.]], 75.);
+
+    // Per-port values take precedence over the shared value.
+    let per_port = psf.replace("\"z0\" 7.500000e1\n", "\"z0\" 75\n\"z0_2\" 5.000000e1\n");
+    let ast = ascii_parse(&per_port).expect("Failed to parse sp PSF file");
+    let data = SpData::from_ascii(&ast).unwrap();
+    assert_eq!(data.z0, vec![75., 50.]);
+    assert!(data
+        .write_touchstone(Vec::new(), TouchstoneFormat::RealImag)
+        .is_err());
+
+    let per_port = psf.replace("\"z0\" 7.500000e1\n", "\"z0(1)\" 50\n\"z0(2)\" 25\n");
+    let ast = ascii_parse(&per_port).expect("Failed to parse sp PSF file");
+    assert_eq!(SpData::from_ascii(&ast).unwrap().z0, vec![50., 25.]);
+
+    // A missing reference impedance is an error rather than a default.
+    let missing = psf.replace("\"z0\" 7.500000e1\n", "\"z0_1\" 50\n");
+    let ast = ascii_parse(&missing).expect("Failed to parse sp PSF file");
+    assert!(SpData::from_ascii(&ast).is_err());
+    let missing = psf.replace("\"z0\" 7.500000e1\n", "");
+    let ast = ascii_parse(&missing).expect("Failed to parse sp PSF file");
+    assert!(SpData::from_ascii(&ast).is_err());
+}
+
+#[cfg(feature = "fft")]
+#[test]
+fn pss_conversions() {
+    use std::f64::consts::PI;
+
+    use crate::analysis::pss::PssTimeData;
+
+    // One period of x(t) = 1 + 2 cos(w t) + 0.5 sin(3 w t) at 1 GHz,
+    // starting away from t = 0.
+    let (f0, t0) = (1e9, 0.25e-9);
+    let time = (0..=256)
+        .map(|i| t0 + i as f64 / 256. / f0)
+        .collect::<Vec<_>>();
+    let x = time
+        .iter()
+        .map(|t| {
+            let w = 2. * PI * f0 * t;
+            1. + 2. * w.cos() + 0.5 * (3. * w).sin()
+        })
+        .collect();
+    let td = PssTimeData {
+        data: TransientData {
+            signals: HashMap::from([("time".to_string(), time), ("x".to_string(), x)]),
+            time: "time".to_string(),
+            units: HashMap::new(),
+        },
+    };
+    assert_float_eq!(td.period().unwrap(), 1e-9, rmax <= 1e-12);
+
+    let fd = td.to_freq(5).unwrap();
+    assert_float_eq!(fd.fundamental.unwrap(), f0, rmax <= 1e-9);
+    assert_eq!(fd.harmonics, vec![0, 1, 2, 3, 4, 5]);
+    let h1 = fd.harmonic("x", 1).unwrap();
+    assert_float_eq!(h1.re, 2., abs <= 1e-3);
+    assert_float_eq!(h1.im, 0., abs <= 1e-3);
+    let h3 = fd.harmonic("x", 3).unwrap();
+    assert_float_eq!(h3.re, 0., abs <= 1e-3);
+    assert_float_eq!(h3.im, -0.5, abs <= 1e-3);
+    assert_float_eq!(fd.harmonic_mag("x", 0).unwrap(), 1., abs <= 1e-3);
+    assert_float_eq!(
+        fd.harmonic_db20("x", 1).unwrap(),
+        20. * 2f64.log10(),
+        abs <= 1e-2
+    );
+    assert!(fd.harmonic("x", 6).is_none());
+
+    let td = fd.to_time(100).unwrap();
+    let x = td.signal("x").unwrap();
+    assert_eq!(x.len(), 101);
+    assert_float_eq!(x[0], 3., abs <= 1e-3);
+    assert_float_eq!(x[25], 1. - 0.5, abs <= 1e-3);
+}
+
+#[test]
+fn pss_harmonic_sweep() {
+    let mut psf = String::from(
+        r#"HEADER
+"PSFversion" "1.00"
+"analysis type" "pss"
+"fundamental" 1.000000e+09
+TYPE
+"sweep" INT PROP(
+"key" "sweep"
+)
+"V" COMPLEX DOUBLE PROP(
+"key" "node"
+"units" "V"
+)
+SWEEP
+"harmonic" "sweep" PROP(
+"key" "harmonic"
+)
+TRACE
+"out" "V"
+VALUE
+"#,
+    );
+    for (k, x) in [(0, 1.), (1, 2.), (2, 0.)] {
+        psf.push_str(&format!(
+            "\"harmonic\" {k}\n\"out\" ({x:.6e} 0.000000e+00)\n"
+        ));
+    }
+    psf.push_str("END\n");
+
+    let ast = ascii_parse(&psf).expect("Failed to parse pss PSF file");
+    let fd = PssFreqData::from_ascii(&ast);
+    assert_eq!(fd.harmonics, vec![0, 1, 2]);
+    assert_eq!(fd.fundamental, Some(1e9));
+    assert_eq!(fd.freq().unwrap(), vec![0., 1e9, 2e9]);
+    assert_eq!(fd.harmonic("out", 1), Some(Complex64::new(2., 0.)));
+
+    let td = fd.to_time(4).unwrap();
+    let x = td.signal("out").unwrap();
+    assert_float_eq!(x[0], 3., abs <= 1e-9);
+    assert_float_eq!(x[2], -1., abs <= 1e-9);
+}
+
+#[test]
+fn pac_sidebands() {
+    let mut psf = String::from(
+        r#"HEADER
+"PSFversion" "1.00"
+"analysis type" "pac"
+TYPE
+"sweep" FLOAT DOUBLE PROP(
+"key" "sweep"
+)
+"V" COMPLEX DOUBLE PROP(
+"key" "node"
+)
+SWEEP
+"sideband" "sweep" PROP(
+"key" "sideband"
+)
+"freq" "sweep" PROP(
+"units" "Hz"
+)
+TRACE
+"rf" "V"
+"if" "V"
+VALUE
+"#,
+    );
+    for (sideband, gain) in [(-1, 0.5), (0, 0.1), (1, 0.01)] {
+        psf.push_str(&format!("\"sideband\" {sideband}\n"));
+        for f in [1e6, 2e6, 3e6] {
+            psf.push_str(&format!(
+                "\"freq\" {f:.6e}\n\"rf\" (1.000000e+00 0.000000e+00)\n\"if\" (0.000000e+00 {gain:.6e})\n"
+            ));
+        }
+    }
+    psf.push_str("END\n");
+
+    let ast = ascii_parse(&psf).expect("Failed to parse pac PSF file");
+    let data = PacData::from_ascii(&ast);
+    assert_eq!(
+        data.sidebands.keys().copied().collect::<Vec<_>>(),
+        vec![-1, 0, 1]
+    );
+    assert_eq!(data.sideband(-1).unwrap().freq, vec![1e6, 2e6, 3e6]);
+    assert_eq!(data.get(1, "if", 2), Some(Complex64::new(0., 0.01)));
+    assert!(data.get(2, "if", 0).is_none());
+
+    let gain = data.conversion_gain("if", -1, "rf", 0).unwrap();
+    assert_eq!(gain.len(), 3);
+    assert_float_eq!(gain[0].norm(), 0.5, rmax <= 1e-12);
+
+    let signal = |id, name| SignalRef {
+        id: TraceId(id),
+        name,
+        type_id: TypeId(0),
+        properties: Properties { values: Vec::new() },
+    };
+    let mut ast = BinaryAst::new();
+    ast.header.values.insert("sideband", BinaryValue::Int(-1));
+    ast.sweeps.push(signal(1, "freq"));
+    ast.traces.push(BinaryTrace::Signal(signal(2, "if")));
+    ast.values.values.extend([
+        (TraceId(1), BinaryValues::Real(vec![1e6, 2e6])),
+        (
+            TraceId(2),
+            BinaryValues::Complex(vec![Complex64::new(0.5, 0.); 2]),
+        ),
+    ]);
+    let data = PacData::from_binary(ast);
+    assert_eq!(data.sidebands.keys().copied().collect::<Vec<_>>(), vec![-1]);
+    assert_eq!(data.get(-1, "if", 1), Some(Complex64::new(0.5, 0.)));
+
+    let pnoise = PnoiseData {
+        data: NoiseData {
+            freq: vec![1e3, 1e6],
+            output: vec![1e-6, 1e-9],
+            input: None,
+            contributions: HashMap::new(),
+        },
+    };
+    let pn = pnoise.phase_noise_dbc(1.);
+    assert_float_eq!(pn[0], -120., abs <= 1e-9);
+    assert_float_eq!(pn[1], -180., abs <= 1e-9);
+}
+
+#[test]
+fn xf_lookup_and_rejection() {
+    let mut psf = String::from(
+        r#"HEADER
+"PSFversion" "1.00"
+"analysis type" "xf"
+TYPE
+"sweep" FLOAT DOUBLE PROP(
+"key" "sweep"
+)
+"V/V" COMPLEX DOUBLE
+"mos_xf" STRUCT(
+"d" COMPLEX DOUBLE
+"g" COMPLEX DOUBLE
+"s" COMPLEX DOUBLE
+) PROP(
+"key" "xf"
+)
+SWEEP
+"freq" "sweep" PROP(
+"units" "Hz"
+)
+TRACE
+"Vinp" "V/V"
+"Vinn" "V/V"
+"Vdd" "V/V"
+"M1" "mos_xf"
+VALUE
+"#,
+    );
+    for f in [1e3, 1e6] {
+        psf.push_str(&format!(
+            "\"freq\" {f:.6e}\n\"Vinp\" (1.010000e+02 0.000000e+00)\n\
+             \"Vinn\" (-9.900000e+01 0.000000e+00)\n\"Vdd\" (1.000000e-01 0.000000e+00)\n\
+             \"M1\" ((1.000000e+00 2.000000e+00) (3.000000e+00 4.000000e+00) (5.000000e+00 6.000000e+00))\n"
+        ));
+    }
+    psf.push_str("END\n");
+
+    let ast = ascii_parse(&psf).expect("Failed to parse xf PSF file");
+    let data = XfData::from_ascii(&ast);
+    assert_eq!(data.freq, vec![1e3, 1e6]);
+    assert_eq!(data.sources.len(), 3);
+    assert_eq!(data.terminals["M1"].len(), 3);
+    assert_eq!(data.terminal("M1", "g").unwrap()[1], Complex64::new(3., 4.));
+    assert_eq!(data.get("M1:s").unwrap()[0], Complex64::new(5., 6.));
+    assert_eq!(data.get("Vdd").unwrap()[0], Complex64::new(0.1, 0.));
+    assert!(data.get("M1:b").is_none());
+
+    // Adm = 100, Acm = 2
+    let cmrr = data.cmrr_db("Vinp", "Vinn").unwrap();
+    assert_float_eq!(cmrr[0], 20. * 50f64.log10(), abs <= 1e-9);
+    let psrr = data.psrr_diff_db("Vinp", "Vinn", "Vdd").unwrap();
+    assert_float_eq!(psrr[0], 60., abs <= 1e-9);
+    let psrr = data.psrr_db("Vinp", "Vdd").unwrap();
+    assert_float_eq!(psrr[1], 20. * 1010f64.log10(), abs <= 1e-9);
+}
+
+#[test]
+fn montecarlo_family() {
+    let mut psf = String::from(
+        r#"HEADER
+"PSFversion" "1.00"
+TYPE
+"sweep" FLOAT DOUBLE
+"V" FLOAT DOUBLE
+SWEEP
+"mcparamset" "sweep"
+"time" "sweep"
+TRACE
+"out" "V"
+VALUE
+"#,
+    );
+    let peaks = [1.0, 1.2, 0.7, 1.1, 1.0];
+    for (i, peak) in peaks.iter().enumerate() {
+        psf.push_str(&format!("\"mcparamset\" {}\n", i + 1));
+        for (t, x) in [(0., 0.), (1e-9, *peak), (2e-9, 0.)] {
+            psf.push_str(&format!("\"time\" {t:.6e}\n\"out\" {x:.6e}\n"));
+        }
+    }
+    psf.push_str("END\n");
+
+    let ast = ascii_parse(&psf).expect("Failed to parse Monte Carlo PSF file");
+    let mc = MonteCarlo::<TransientData>::from_ascii_family(&ast, MC_SWEEP).unwrap();
+    assert_eq!(mc.iterations, vec![1, 2, 3, 4, 5]);
+    assert_eq!(mc.runs[2].signal("time").unwrap().len(), 3);
+
+    let peak = mc.measure(|run| Some(run.stats("out", 0., 2e-9)?.max));
+    let stats = peak.stats().unwrap();
+    assert_eq!(stats.count, 5);
+    assert_float_eq!(stats.mean, 1.0, abs <= 1e-12);
+    assert_float_eq!(stats.std_dev, 0.035f64.sqrt(), abs <= 1e-12);
+    assert_eq!((stats.min, stats.min_iteration), (0.7, 3));
+    assert_eq!((stats.max, stats.max_iteration), (1.2, 2));
+    assert!(stats.failed.is_empty());
+
+    assert_eq!(peak.quantile(0.5), Some(1.0));
+    assert_float_eq!(peak.quantile(0.1).unwrap(), 0.82, abs <= 1e-12);
+    assert_eq!(peak.quantiles(&[0., 1.]), Some(vec![0.7, 1.2]));
+    let hist = peak.histogram(5).unwrap();
+    assert_eq!(hist.edges.len(), 6);
+    assert_eq!(hist.counts, vec![1, 0, 0, 2, 2]);
+    assert_eq!(peak.worst_case(Worst::Min), Some((3, 0.7)));
+    assert_eq!(peak.worst_case(Worst::FarthestFrom(1.1)), Some((3, 0.7)));
+
+    let missing = mc.measure(|run| Some(run.stats("missing", 0., 2e-9)?.max));
+    assert!(missing.stats().is_none());
+}
+
+#[test]
+fn montecarlo_load() {
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/examples");
+    let mc = MonteCarlo::<TransientData>::load([
+        format!("{dir}/vdiv_sin_ascii.tran.tran"),
+        format!("{dir}/vdiv_sin_bin.tran.tran"),
+    ])
+    .unwrap();
+    assert_eq!(mc.iterations, vec![1, 2]);
+    let rms = mc.measure(|run| Some(run.stats("out", 0., 1e-6)?.rms));
+    let stats = rms.stats().unwrap();
+    assert_float_eq!(stats.min, stats.max, rmax <= 1e-9);
+}
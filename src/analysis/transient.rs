@@ -1,14 +1,44 @@
 use std::collections::HashMap;
 
-use crate::ascii::ast::{PsfAst as AsciiAst, Trace, Values};
+use crate::analysis::digital::{self, Bus, Edge, Threshold};
+use crate::ascii::ast::{PsfAst as AsciiAst, Values};
 use crate::bin_search_before;
 use crate::binary::ast::PsfAst as BinaryAst;
+use crate::raw::{Plot, RawValues};
+use crate::Result;
+use anyhow::{anyhow, bail};
 use float_eq::float_eq;
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TransientData {
     pub signals: HashMap<String, Vec<f64>>,
     pub time: String,
+    /// The units of each signal, where known.
+    pub units: HashMap<String, String>,
+}
+
+/// Statistics of a transient signal over a time window.
+///
+/// Produced by [`TransientData::stats`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SignalStats {
+    /// The minimum value in the window.
+    pub min: f64,
+    /// The time at which the minimum value occurs.
+    pub min_time: f64,
+    /// The maximum value in the window.
+    pub max: f64,
+    /// The time at which the maximum value occurs.
+    pub max_time: f64,
+    /// The time-weighted average.
+    pub mean: f64,
+    /// The true (time-weighted) root mean square value.
+    pub rms: f64,
+    /// The integral of the signal over the window.
+    pub integral: f64,
+    /// The time-weighted standard deviation.
+    pub std_dev: f64,
 }
 
 impl TransientData {
@@ -41,71 +71,101 @@ impl TransientData {
         true
     }
 
-    pub fn from_binary(mut ast: BinaryAst) -> Self {
+    /// Reads transient data from a binary PSF.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the file is not a valid transient analysis;
+    /// see [`Self::try_from_binary`].
+    pub fn from_binary(ast: BinaryAst) -> Self {
+        Self::try_from_binary(ast).unwrap()
+    }
+
+    /// Reads transient data from a binary PSF.
+    ///
+    /// Fails if any signal or sweep is missing or complex.
+    pub fn try_from_binary(mut ast: BinaryAst) -> Result<Self> {
         let mut signals = HashMap::new();
         for trace in ast.traces.iter() {
-            for sig in trace.group().signals.iter() {
-                let data = ast.values.values.remove(&sig.id).unwrap().unwrap_real();
-                signals.insert(sig.name.to_string(), data);
+            for sig in trace.signals() {
+                signals.insert(sig.name.to_string(), ast.values.take_real(sig)?);
             }
         }
 
         for swp in ast.sweeps.iter() {
-            let data = ast.values.values.remove(&swp.id).unwrap().unwrap_real();
-            signals.insert(swp.name.to_string(), data);
+            signals.insert(swp.name.to_string(), ast.values.take_real(swp)?);
         }
+        let units = ast.signal_units();
 
-        Self {
+        Ok(Self {
             signals,
             time: "time".to_string(),
-        }
+            units,
+        })
     }
 
+    /// Reads a real-valued SPICE raw file plot whose first variable is time.
+    pub fn from_raw(plot: Plot) -> Result<Self> {
+        let RawValues::Real(values) = plot.values else {
+            bail!("expected real raw file values; found complex");
+        };
+        let Some(time) = plot.variables.first() else {
+            bail!("raw file plot has no variables");
+        };
+        let time = time.name.clone();
+        let units = plot
+            .variables
+            .iter()
+            .filter_map(|var| Some((var.name.clone(), var.units()?.to_string())))
+            .collect();
+        let signals = plot
+            .variables
+            .into_iter()
+            .map(|var| var.name)
+            .zip(values)
+            .collect();
+
+        Ok(Self {
+            signals,
+            time,
+            units,
+        })
+    }
+
+    /// Reads transient data from an ASCII PSF.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the file is not a valid transient analysis;
+    /// see [`Self::try_from_ascii`].
     pub fn from_ascii(ast: &AsciiAst) -> Self {
-        // Assume all groups have count = 1
-        // group name -> signal name
-        let mut groups = HashMap::<&str, &str>::new();
-        let mut i = 0;
-        while i < ast.traces.len() {
-            match ast.traces[i] {
-                Trace::Group { name: group, count } => {
-                    debug_assert!(count >= 0);
-                    let count = count as usize;
-                    for j in 1..=count {
-                        if let Trace::Signal { name, .. } = ast.traces[i + j] {
-                            groups.insert(group, name);
-                        } else {
-                            panic!("Expected signal; found group");
-                        }
-                    }
-                    i += count + 1;
-                }
-                Trace::Signal { name, .. } => {
-                    groups.insert(name, name);
-                    i += 1;
-                }
-            }
-        }
+        Self::try_from_ascii(ast).unwrap()
+    }
+
+    /// Reads transient data from an ASCII PSF.
+    ///
+    /// Fails if any signal is not real.
+    pub fn try_from_ascii(ast: &AsciiAst) -> Result<Self> {
+        let mut groups = ast.groups();
 
         groups.insert("time", "time");
         let mut signals = HashMap::<String, Vec<f64>>::new();
         for v in ast.values.iter() {
-            if let Values::Real(values) = &v.values {
-                debug_assert_eq!(values.len(), 1);
-                if let Some(lst) = signals.get_mut(groups[v.signal]) {
-                    lst.push(values[0]);
-                } else {
-                    signals.insert(groups[v.signal].to_string(), vec![values[0]]);
-                }
-            } else {
-                panic!("Expected real signal values; found complex");
-            }
+            let Values::Real(values) = &v.values else {
+                bail!("expected real values for signal `{}`", v.signal);
+            };
+            debug_assert_eq!(values.len(), 1);
+            let Some(name) = groups.get(v.signal) else {
+                bail!("values for undeclared signal `{}`", v.signal);
+            };
+            signals.entry(name.to_string()).or_default().push(values[0]);
         }
 
-        Self {
+        Ok(Self {
             signals,
             time: "time".to_string(),
-        }
+            units: ast.signal_units(),
+        })
     }
 
     /// Gets the index into the data arrays
@@ -118,4 +178,442 @@ impl TransientData {
     pub fn signal(&self, name: &str) -> Option<&Vec<f64>> {
         self.signals.get(name)
     }
+
+    /// Gets the times at which the signal `name` has the given kind of edge,
+    /// after converting it to logic levels with `threshold`.
+    ///
+    /// Returns `None` if the signal does not exist.
+    pub fn edges(&self, name: &str, threshold: Threshold, edge: Edge) -> Option<Vec<f64>> {
+        let time = self.signal(&self.time)?;
+        let values = self.signal(name)?;
+        Some(digital::edges(&threshold.digitize(time, values), edge))
+    }
+
+    /// Finds the bits of the bus `name`, such as the signals `v(dout[0])` to
+    /// `v(dout[7])` for the bus `dout`, and converts them to logic levels with `threshold`.
+    ///
+    /// The bit indices must be contiguous; the lowest index is the least significant bit.
+    /// Sample the bus with [`Bus::codes`], for example at the times given by [`Self::edges`].
+    pub fn bus(&self, name: &str, threshold: Threshold) -> Result<Bus> {
+        let time = self
+            .signal(&self.time)
+            .ok_or_else(|| anyhow!("missing time signal `{}`", self.time))?;
+        let mut bits = self
+            .signals
+            .iter()
+            .filter_map(|(signal, values)| match digital::bus_bit(signal) {
+                Some((bus, index)) if bus == name => Some((index, signal, values)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        bits.sort_by_key(|(index, _, _)| *index);
+
+        let Some(&(lsb, _, _)) = bits.first() else {
+            bail!("no signals found for bus `{name}`");
+        };
+        if bits.len() > 63 {
+            bail!("bus `{name}` is wider than 63 bits");
+        }
+        for (i, (index, signal, values)) in bits.iter().enumerate() {
+            if i > 0 && *index == bits[i - 1].0 {
+                bail!("bus `{name}` has more than one signal for bit {index}");
+            }
+            if *index != lsb + i {
+                bail!("bus `{name}` is missing bit {}", lsb + i);
+            }
+            if values.len() != time.len() {
+                bail!("signal `{signal}` does not have the same number of points as time");
+            }
+        }
+
+        Ok(Bus {
+            signals: bits
+                .iter()
+                .map(|(_, signal, _)| signal.to_string())
+                .collect(),
+            bits: bits
+                .iter()
+                .map(|(_, _, values)| threshold.digitize(time, values))
+                .collect(),
+        })
+    }
+
+    /// Computes statistics of the signal `name` over the window `[t_start, t_stop]`.
+    ///
+    /// Spectre uses adaptive timesteps, so all averages are weighted by the time axis
+    /// rather than computed over raw samples. The signal is treated as piecewise linear
+    /// between samples, and its values at the window edges are linearly interpolated.
+    ///
+    /// Returns `None` if the signal does not exist or if the window does not
+    /// overlap the simulated time range.
+    pub fn stats(&self, name: &str, t_start: f64, t_stop: f64) -> Option<SignalStats> {
+        let time = self.signal(&self.time)?;
+        let values = self.signal(name)?;
+        let (t, x) = window(time, values, t_start, t_stop)?;
+
+        let (mut min, mut min_time) = (x[0], t[0]);
+        let (mut max, mut max_time) = (x[0], t[0]);
+        for (&ti, &xi) in t.iter().zip(x.iter()) {
+            if xi < min {
+                (min, min_time) = (xi, ti);
+            }
+            if xi > max {
+                (max, max_time) = (xi, ti);
+            }
+        }
+
+        let integral = trapz(&t, &x);
+        let mut integral_sq = 0.;
+        for i in 1..t.len() {
+            let dt = t[i] - t[i - 1];
+            let (a, b) = (x[i - 1], x[i]);
+            // Exact integral of the square of a linear segment.
+            integral_sq += dt * (a * a + a * b + b * b) / 3.;
+        }
+
+        let duration = t[t.len() - 1] - t[0];
+        let (mean, mean_sq) = if duration > 0. {
+            (integral / duration, integral_sq / duration)
+        } else {
+            (x[0], x[0] * x[0])
+        };
+
+        Some(SignalStats {
+            min,
+            min_time,
+            max,
+            max_time,
+            mean,
+            rms: mean_sq.sqrt(),
+            integral,
+            std_dev: (mean_sq - mean * mean).max(0.).sqrt(),
+        })
+    }
+
+    /// Resamples all signals onto a uniform time grid spanning the simulated time range.
+    ///
+    /// Fails if the time signal is missing, the grid is invalid,
+    /// or any signal does not have the same number of points as time.
+    pub fn resample(&self, grid: Grid, interp: Interp) -> Result<Self> {
+        let names = self
+            .signals
+            .keys()
+            .filter(|name| **name != self.time)
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        self.resample_signals(&names, grid, interp)
+    }
+
+    /// Resamples the given signals onto a uniform time grid spanning the simulated time range.
+    ///
+    /// The returned data contains only the time signal and the requested signals.
+    /// Fails if any signal is missing or does not have the same number of points as time,
+    /// or if the grid is invalid.
+    pub fn resample_signals(&self, names: &[&str], grid: Grid, interp: Interp) -> Result<Self> {
+        let time = self
+            .signal(&self.time)
+            .ok_or_else(|| anyhow!("missing time signal `{}`", self.time))?;
+        let (Some(&t_start), Some(&t_stop)) = (time.first(), time.last()) else {
+            bail!("cannot resample empty transient data");
+        };
+        let grid = grid
+            .points(t_start, t_stop)
+            .ok_or_else(|| anyhow!("invalid resampling grid {grid:?}"))?;
+
+        let mut signals = HashMap::with_capacity(names.len() + 1);
+        for &name in names {
+            let values = self
+                .signal(name)
+                .ok_or_else(|| anyhow!("no signal named `{name}`"))?;
+            if values.len() != time.len() {
+                bail!("signal `{name}` does not have the same number of points as time");
+            }
+            signals.insert(name.to_string(), interp.sample(time, values, &grid));
+        }
+        signals.insert(self.time.clone(), grid);
+        let units = self
+            .units
+            .iter()
+            .filter(|(name, _)| signals.contains_key(*name))
+            .map(|(name, u)| (name.clone(), u.clone()))
+            .collect();
+
+        Ok(Self {
+            signals,
+            time: self.time.clone(),
+            units,
+        })
+    }
+
+    /// Concatenates segments of one logical run, such as a transient restarted
+    /// from a saved state or split into several simulations.
+    ///
+    /// Segments are given in the order they were simulated. Where a segment
+    /// starts before the previous ones end, the later segment takes precedence:
+    /// earlier samples at or after its start time are discarded.
+    ///
+    /// Fails if no segments are given, or if the segments do not have the same
+    /// time signal, the same set of signals and matching units where known.
+    pub fn concat(parts: &[TransientData]) -> Result<Self> {
+        let Some(first) = parts.first() else {
+            bail!("no transient data to concatenate");
+        };
+        let units = merge_units(parts)?;
+
+        let mut signals = first
+            .signals
+            .keys()
+            .map(|name| (name.clone(), Vec::new()))
+            .collect::<HashMap<_, Vec<f64>>>();
+        for part in parts {
+            if part.time != first.time {
+                bail!(
+                    "time signals `{}` and `{}` do not match",
+                    first.time,
+                    part.time
+                );
+            }
+            if part.signals.len() != first.signals.len()
+                || part.signals.keys().any(|name| !signals.contains_key(name))
+            {
+                bail!("segments do not contain the same signals");
+            }
+            let time = part
+                .signal(&part.time)
+                .ok_or_else(|| anyhow!("missing time signal `{}`", part.time))?;
+            let Some(&start) = time.first() else {
+                continue;
+            };
+
+            // Drop earlier samples that this segment overrides.
+            let keep = signals[&first.time].partition_point(|&t| t < start);
+            for (name, values) in signals.iter_mut() {
+                let new = &part.signals[name];
+                if new.len() != time.len() {
+                    bail!("signal `{name}` does not have one value per time point");
+                }
+                values.truncate(keep);
+                values.extend_from_slice(new);
+            }
+        }
+
+        Ok(Self {
+            signals,
+            time: first.time.clone(),
+            units,
+        })
+    }
+
+    /// Merges different signals saved over the same time axis,
+    /// such as separate saves from one simulation.
+    ///
+    /// Time points must match to a relative tolerance of `1e-6`,
+    /// the precision of ASCII PSF files. Signals present in several inputs
+    /// are taken from the last input that contains them.
+    ///
+    /// Fails if no inputs are given, or if the inputs have different time axes
+    /// or conflicting units.
+    pub fn merge_signals(parts: &[TransientData]) -> Result<Self> {
+        let Some(first) = parts.first() else {
+            bail!("no transient data to merge");
+        };
+        let units = merge_units(parts)?;
+        let time = first
+            .signal(&first.time)
+            .ok_or_else(|| anyhow!("missing time signal `{}`", first.time))?;
+
+        let mut signals = HashMap::new();
+        for part in parts {
+            let other = part
+                .signal(&part.time)
+                .ok_or_else(|| anyhow!("missing time signal `{}`", part.time))?;
+            if part.time != first.time
+                || other.len() != time.len()
+                || !time
+                    .iter()
+                    .zip(other.iter())
+                    .all(|(t1, t2)| float_eq!(t1, t2, r2nd <= 1e-6))
+            {
+                bail!("time axes do not match");
+            }
+            for (name, values) in part.signals.iter() {
+                if *name != part.time {
+                    signals.insert(name.clone(), values.clone());
+                }
+            }
+        }
+        signals.insert(first.time.clone(), time.clone());
+
+        Ok(Self {
+            signals,
+            time: first.time.clone(),
+            units,
+        })
+    }
+}
+
+/// Combines the units of several results, failing if any signal has conflicting units.
+fn merge_units(parts: &[TransientData]) -> Result<HashMap<String, String>> {
+    let mut units = HashMap::<String, String>::new();
+    for part in parts {
+        for (name, u) in part.units.iter() {
+            match units.get(name) {
+                Some(prev) if prev != u => {
+                    bail!("signal `{name}` has conflicting units `{prev}` and `{u}`");
+                }
+                Some(_) => {}
+                None => {
+                    units.insert(name.clone(), u.clone());
+                }
+            }
+        }
+    }
+    Ok(units)
+}
+
+/// The spacing of a uniform time grid.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Grid {
+    /// A fixed timestep, starting at the first simulated time point.
+    Step(f64),
+    /// A fixed number of points, including both endpoints of the simulated time range.
+    Points(usize),
+}
+
+impl Grid {
+    fn points(self, t_start: f64, t_stop: f64) -> Option<Vec<f64>> {
+        let span = t_stop - t_start;
+        let (n, dt) = match self {
+            Self::Step(dt) => {
+                if dt <= 0. || !dt.is_finite() || !span.is_finite() {
+                    return None;
+                }
+                // Tolerate rounding error when the span is a multiple of the timestep.
+                ((span / dt + 1e-9).floor() as usize + 1, dt)
+            }
+            Self::Points(n) => {
+                if n < 2 {
+                    return None;
+                }
+                (n, span / (n - 1) as f64)
+            }
+        };
+        Some((0..n).map(|i| t_start + i as f64 * dt).collect())
+    }
+}
+
+/// The method used to compute signal values at resampled time points.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub enum Interp {
+    /// Holds the value of the most recent sample.
+    Previous,
+    /// Linearly interpolates between neighboring samples.
+    #[default]
+    Linear,
+    /// Averages the signal over each output interval, which extends halfway
+    /// to the neighboring points.
+    ///
+    /// This is a crude boxcar filter that attenuates, but does not remove, aliasing.
+    Average,
+}
+
+impl Interp {
+    fn sample(self, time: &[f64], values: &[f64], grid: &[f64]) -> Vec<f64> {
+        match self {
+            Self::Previous => grid
+                .iter()
+                .map(|&t| {
+                    let i = time.partition_point(|&x| x <= t);
+                    values[i.saturating_sub(1)]
+                })
+                .collect(),
+            Self::Linear => grid.iter().map(|&t| interp(time, values, t)).collect(),
+            Self::Average => (0..grid.len())
+                .map(|i| {
+                    let t = grid[i];
+                    // Each interval extends halfway to the neighboring points,
+                    // and the first and last are symmetric about their points.
+                    let n = grid.len();
+                    let (before, after) = if n > 1 {
+                        let spacing = |j: usize| grid[j + 1] - grid[j];
+                        (
+                            spacing(i.saturating_sub(1).min(n - 2)),
+                            spacing(i.min(n - 2)),
+                        )
+                    } else {
+                        (0., 0.)
+                    };
+                    match window(time, values, t - 0.5 * before, t + 0.5 * after) {
+                        Some((t, x)) if t[t.len() - 1] > t[0] => {
+                            trapz(&t, &x) / (t[t.len() - 1] - t[0])
+                        }
+                        _ => interp(time, values, t),
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Integrates a piecewise linear waveform using the trapezoidal rule.
+pub(crate) fn trapz(time: &[f64], values: &[f64]) -> f64 {
+    time.windows(2)
+        .zip(values.windows(2))
+        .map(|(t, x)| 0.5 * (t[1] - t[0]) * (x[0] + x[1]))
+        .sum()
+}
+
+/// Linearly interpolates `values`, sampled at the ascending points `time`, at `t`.
+///
+/// Values outside the sampled range are clamped to the first or last sample.
+pub(crate) fn interp(time: &[f64], values: &[f64], t: f64) -> f64 {
+    let i = time.partition_point(|&x| x <= t);
+    if i == 0 {
+        return values[0];
+    }
+    if i == time.len() {
+        return values[i - 1];
+    }
+    let (t0, t1) = (time[i - 1], time[i]);
+    let (x0, x1) = (values[i - 1], values[i]);
+    if t1 == t0 {
+        return x1;
+    }
+    x0 + (x1 - x0) * (t - t0) / (t1 - t0)
+}
+
+/// Restricts a waveform to the window `[t_start, t_stop]`,
+/// interpolating its values at the window edges.
+pub(crate) fn window(
+    time: &[f64],
+    values: &[f64],
+    t_start: f64,
+    t_stop: f64,
+) -> Option<(Vec<f64>, Vec<f64>)> {
+    if time.is_empty() || time.len() != values.len() {
+        return None;
+    }
+    let t_start = t_start.max(time[0]);
+    let t_stop = t_stop.min(time[time.len() - 1]);
+    if t_start > t_stop {
+        return None;
+    }
+
+    let lo = time.partition_point(|&x| x <= t_start);
+    let hi = time.partition_point(|&x| x < t_stop);
+
+    let mut t = Vec::with_capacity(hi.saturating_sub(lo) + 2);
+    let mut x = Vec::with_capacity(t.capacity());
+    t.push(t_start);
+    x.push(interp(time, values, t_start));
+    if lo < hi {
+        t.extend_from_slice(&time[lo..hi]);
+        x.extend_from_slice(&values[lo..hi]);
+    }
+    if t_stop > t_start {
+        t.push(t_stop);
+        x.push(interp(time, values, t_stop));
+    }
+
+    Some((t, x))
 }
@@ -0,0 +1,225 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail};
+use num::complex::Complex64;
+
+use crate::ascii::ast::{Kind, PsfAst as AsciiAst, Trace, Values};
+use crate::binary::ast::PsfAst as BinaryAst;
+use crate::Result;
+
+/// Results of a Spectre transfer function (`xf`) analysis.
+///
+/// Each entry is the transfer function from a source to the analysis output.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct XfData {
+    pub freq: Vec<f64>,
+    /// Transfer functions from independent sources, keyed by instance name.
+    pub sources: HashMap<String, Vec<Complex64>>,
+    /// Transfer functions from the terminals of multi-terminal instances,
+    /// keyed by instance name and then terminal name.
+    pub terminals: HashMap<String, HashMap<String, Vec<Complex64>>>,
+}
+
+impl XfData {
+    /// Reads transfer functions from an ASCII PSF.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the file is not a valid xf analysis; see [`Self::try_from_ascii`].
+    pub fn from_ascii(ast: &AsciiAst) -> Self {
+        Self::try_from_ascii(ast).unwrap()
+    }
+
+    /// Reads transfer functions from an ASCII PSF.
+    ///
+    /// Fails if any signal has values of the wrong kind or too few struct fields.
+    pub fn try_from_ascii(ast: &AsciiAst) -> Result<Self> {
+        // type name -> (field name, is complex)
+        let mut structs = HashMap::<&str, Vec<(&str, bool)>>::new();
+        for ty in ast.types.iter() {
+            for kind in ty.kinds.iter() {
+                if let Kind::Struct(fields) = kind {
+                    let fields = fields
+                        .iter()
+                        .map(|f| (f.name, f.kinds.contains(&Kind::Complex)))
+                        .collect();
+                    structs.insert(ty.name, fields);
+                }
+            }
+        }
+
+        // signal name -> struct fields
+        let mut fields = HashMap::<&str, &[(&str, bool)]>::new();
+        for trace in ast.traces.iter() {
+            if let Trace::Signal { name, units } = trace {
+                if let Some(f) = structs.get(units) {
+                    fields.insert(name, f);
+                }
+            }
+        }
+
+        let mut data = Self::default();
+        for v in ast.values.iter() {
+            if let Some(fields) = fields.get(v.signal) {
+                let Values::Struct(values) = &v.values else {
+                    bail!("expected struct values for signal `{}`", v.signal);
+                };
+                debug_assert_eq!(values.len(), 1);
+                let terminals = data.terminals.entry(v.signal.to_string()).or_default();
+                let mut values = values[0].iter().copied();
+                let too_few = || anyhow!("too few struct fields for signal `{}`", v.signal);
+                for (name, complex) in fields.iter() {
+                    let re = values.next().ok_or_else(too_few)?;
+                    let im = if *complex {
+                        values.next().ok_or_else(too_few)?
+                    } else {
+                        0.
+                    };
+                    terminals
+                        .entry(name.to_string())
+                        .or_default()
+                        .push(Complex64::new(re, im));
+                }
+            } else {
+                match &v.values {
+                    Values::Real(values) if v.signal == "freq" => {
+                        debug_assert_eq!(values.len(), 1);
+                        data.freq.push(values[0]);
+                    }
+                    Values::Complex(values) => {
+                        debug_assert_eq!(values.len(), 1);
+                        data.sources
+                            .entry(v.signal.to_string())
+                            .or_default()
+                            .push(values[0]);
+                    }
+                    _ => bail!("expected complex values for signal `{}`", v.signal),
+                }
+            }
+        }
+
+        Ok(data)
+    }
+
+    /// Reads transfer functions from a binary PSF.
+    ///
+    /// The binary parser does not support struct-typed traces,
+    /// so only independent sources are available.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the file is not a valid xf analysis; see [`Self::try_from_binary`].
+    pub fn from_binary(ast: BinaryAst) -> Self {
+        Self::try_from_binary(ast).unwrap()
+    }
+
+    /// Reads transfer functions from a binary PSF.
+    ///
+    /// Fails if the file is not swept over frequency,
+    /// or any source is missing or not complex.
+    pub fn try_from_binary(mut ast: BinaryAst) -> Result<Self> {
+        let Some(sweep) = ast.sweeps.first() else {
+            bail!("xf analysis has no sweep");
+        };
+        if sweep.name != "freq" {
+            bail!(
+                "xf analysis expects to sweep frequency; found `{}`",
+                sweep.name
+            );
+        }
+        let freq = ast.values.take_real(sweep)?;
+
+        let mut sources = HashMap::new();
+        for group in ast.traces.iter() {
+            for sig in group.signals() {
+                sources.insert(sig.name.to_string(), ast.values.take_complex(sig)?);
+            }
+        }
+
+        Ok(Self {
+            freq,
+            sources,
+            terminals: HashMap::new(),
+        })
+    }
+
+    /// Gets the transfer function from an independent source.
+    #[inline]
+    pub fn source(&self, instance: &str) -> Option<&Vec<Complex64>> {
+        self.sources.get(instance)
+    }
+
+    /// Gets the transfer function from a terminal of a multi-terminal instance.
+    #[inline]
+    pub fn terminal(&self, instance: &str, terminal: &str) -> Option<&Vec<Complex64>> {
+        self.terminals.get(instance)?.get(terminal)
+    }
+
+    /// Gets a transfer function by name.
+    ///
+    /// Names of the form `instance:terminal` refer to terminals of
+    /// multi-terminal instances; all other names refer to independent sources.
+    pub fn get(&self, name: &str) -> Option<&Vec<Complex64>> {
+        self.source(name).or_else(|| {
+            let (instance, terminal) = name.rsplit_once(':')?;
+            self.terminal(instance, terminal)
+        })
+    }
+
+    /// The power supply rejection ratio at each frequency, in dB.
+    ///
+    /// Computed as the ratio of the gain from `input` to the gain from `supply`.
+    pub fn psrr_db(&self, input: &str, supply: &str) -> Option<Vec<f64>> {
+        let input = self.get(input)?;
+        let supply = self.get(supply)?;
+        Some(
+            input
+                .iter()
+                .zip(supply.iter())
+                .map(|(a, s)| 20. * (a.norm() / s.norm()).log10())
+                .collect(),
+        )
+    }
+
+    /// The power supply rejection ratio of a differential input at each frequency, in dB.
+    ///
+    /// Computed as the ratio of the differential mode gain from `pos` and `neg`
+    /// to the gain from `supply`.
+    pub fn psrr_diff_db(&self, pos: &str, neg: &str, supply: &str) -> Option<Vec<f64>> {
+        let adm = self.differential_gain(pos, neg)?;
+        let supply = self.get(supply)?;
+        Some(
+            adm.iter()
+                .zip(supply.iter())
+                .map(|(a, s)| 20. * (a.norm() / s.norm()).log10())
+                .collect(),
+        )
+    }
+
+    /// The common mode rejection ratio of a differential input at each frequency, in dB.
+    ///
+    /// With transfer functions `Hp` and `Hn` from the sources at the positive and
+    /// negative inputs, the differential mode gain is `(Hp - Hn) / 2` and the
+    /// common mode gain is `Hp + Hn`.
+    pub fn cmrr_db(&self, pos: &str, neg: &str) -> Option<Vec<f64>> {
+        let adm = self.differential_gain(pos, neg)?;
+        let (hp, hn) = (self.get(pos)?, self.get(neg)?);
+        Some(
+            adm.iter()
+                .zip(hp.iter().zip(hn.iter()))
+                .map(|(a, (p, n))| 20. * (a.norm() / (p + n).norm()).log10())
+                .collect(),
+        )
+    }
+
+    /// The differential mode gain `(Hp - Hn) / 2` from sources at a pair of inputs.
+    fn differential_gain(&self, pos: &str, neg: &str) -> Option<Vec<Complex64>> {
+        let (hp, hn) = (self.get(pos)?, self.get(neg)?);
+        Some(
+            hp.iter()
+                .zip(hn.iter())
+                .map(|(p, n)| (p - n) / 2.)
+                .collect(),
+        )
+    }
+}
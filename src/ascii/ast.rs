@@ -1,6 +1,9 @@
+use std::collections::HashMap;
+
 use num::complex::Complex64;
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct PsfAst<'a> {
     pub header: Header<'a>,
     pub types: Vec<TypeDef<'a>>,
@@ -9,24 +12,103 @@ pub struct PsfAst<'a> {
     pub values: Vec<SignalValues<'a>>,
 }
 
+impl<'a> PsfAst<'a> {
+    /// Maps the name of each trace group to the name of the signal it contains.
+    ///
+    /// All groups are assumed to contain one signal.
+    /// Signals outside of any group map to themselves.
+    pub fn groups(&self) -> HashMap<&'a str, &'a str> {
+        let mut groups = HashMap::new();
+        let mut i = 0;
+        while i < self.traces.len() {
+            match self.traces[i] {
+                Trace::Group { name: group, count } => {
+                    debug_assert!(count >= 0);
+                    let count = count as usize;
+                    for j in 1..=count {
+                        if let Trace::Signal { name, .. } = self.traces[i + j] {
+                            groups.insert(group, name);
+                        } else {
+                            panic!("Expected signal; found group");
+                        }
+                    }
+                    i += count + 1;
+                }
+                Trace::Signal { name, .. } => {
+                    groups.insert(name, name);
+                    i += 1;
+                }
+            }
+        }
+        groups
+    }
+
+    /// Resolves the units of a trace from the `units` property of its type, named `type_name`.
+    pub fn units(&self, type_name: &str) -> Option<&'a str> {
+        self.types
+            .iter()
+            .find(|t| t.name == type_name)
+            .and_then(|t| t.prop("units")?.str())
+    }
+
+    /// Resolves the units of every trace and sweep, keyed by signal name.
+    pub fn signal_units(&self) -> HashMap<String, String> {
+        let mut units = HashMap::new();
+        for trace in self.traces.iter() {
+            if let Trace::Signal { name, units: ty } = trace {
+                if let Some(u) = self.units(ty) {
+                    units.insert(name.to_string(), u.to_string());
+                }
+            }
+        }
+        for swp in self.sweeps.iter() {
+            if let Some(u) = swp.prop("units").and_then(Value::str) {
+                units.insert(swp.name.to_string(), u.to_string());
+            }
+        }
+        units
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Header<'a> {
     pub values: Vec<NamedValue<'a>>,
 }
 
+impl Header<'_> {
+    /// Formats every header value as a string, keyed by name.
+    pub fn to_strings(&self) -> HashMap<String, String> {
+        self.values
+            .iter()
+            .map(|v| (v.name.to_string(), v.value.to_string()))
+            .collect()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct TypeDef<'a> {
     pub name: &'a str,
     pub kinds: Vec<Kind<'a>>,
 }
 
+impl<'a> TypeDef<'a> {
+    #[inline]
+    pub fn prop(&self, name: &str) -> Option<&Value<'a>> {
+        find_prop(&self.kinds, name)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct NamedValue<'a> {
     pub name: &'a str,
     pub value: Value<'a>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Value<'a> {
     Int(i64),
     Real(f64),
@@ -34,14 +116,55 @@ pub enum Value<'a> {
     NaN,
 }
 
+impl<'a> Value<'a> {
+    pub fn str(&self) -> Option<&'a str> {
+        match self {
+            Self::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Value<'_> {
+    /// Formats the value as it would appear in a PSF header, without quotes.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Int(x) => write!(f, "{x}"),
+            Self::Real(x) => write!(f, "{x}"),
+            Self::Str(s) => f.write_str(s),
+            Self::NaN => f.write_str("NaN"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Sweep<'a> {
     pub name: &'a str,
     pub sweep_type: &'a str,
     pub kinds: Vec<Kind<'a>>,
 }
 
+impl<'a> Sweep<'a> {
+    #[inline]
+    pub fn prop(&self, name: &str) -> Option<&Value<'a>> {
+        find_prop(&self.kinds, name)
+    }
+}
+
+fn find_prop<'a, 'b>(kinds: &'b [Kind<'a>], name: &str) -> Option<&'b Value<'a>> {
+    kinds.iter().find_map(|kind| match kind {
+        Kind::Prop(prop) => prop
+            .values
+            .iter()
+            .find(|v| v.name == name)
+            .map(|v| &v.value),
+        _ => None,
+    })
+}
+
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Kind<'a> {
     Float,
     Double,
@@ -57,17 +180,20 @@ pub enum Kind<'a> {
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Trace<'a> {
     Group { name: &'a str, count: i64 },
     Signal { name: &'a str, units: &'a str },
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Prop<'a> {
     pub values: Vec<NamedValue<'a>>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct SignalValues<'a> {
     pub signal: &'a str,
     pub sigtype: Option<&'a str>,
@@ -75,7 +201,32 @@ pub struct SignalValues<'a> {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub enum Values {
     Complex(Vec<Complex64>),
     Real(Vec<f64>),
+    /// The fields of struct-typed values, in declaration order.
+    ///
+    /// Complex fields occupy two consecutive entries.
+    Struct(Vec<Vec<f64>>),
+    /// A struct-typed value containing non-numeric fields.
+    Mixed(Vec<Field>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Field {
+    Str(String),
+    Real(f64),
+    List(Vec<Field>),
+}
+
+impl Field {
+    pub fn str(&self) -> Option<&str> {
+        match self {
+            Self::Str(s) => Some(s),
+            _ => None,
+        }
+    }
 }
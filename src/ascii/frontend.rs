@@ -1,9 +1,12 @@
+use std::collections::HashMap;
+
+use anyhow::bail;
 use num::complex::Complex64;
 use pest::iterators::Pair;
 use pest::Parser;
 
 use crate::ascii::ast::{
-    Header, Kind, NamedValue, Prop, PsfAst, SignalValues, Sweep, Trace, TypeDef, Value,
+    Field, Header, Kind, NamedValue, Prop, PsfAst, SignalValues, Sweep, Trace, TypeDef, Value,
 };
 use crate::Result;
 
@@ -13,7 +16,7 @@ use super::ast::Values;
 #[grammar = "ascii/psf_ascii.pest"]
 pub struct PsfAsciiParser;
 
-pub fn parse(input: &str) -> Result<PsfAst> {
+pub fn parse(input: &str) -> Result<PsfAst<'_>> {
     let input = PsfAsciiParser::parse(Rule::psf_ascii, input)?
         .next()
         .unwrap();
@@ -40,7 +43,8 @@ fn parse_psf_inner(input: Pair<Rule>) -> Result<PsfAst> {
                 traces.extend(parse_traces(input.into_inner().next().unwrap())?);
             }
             Rule::value_section => {
-                values.extend(parse_value_section(input)?);
+                let composites = Composites::new(&types, &traces);
+                values.extend(parse_value_section(input, &composites)?);
             }
             _ => break,
         }
@@ -89,7 +93,7 @@ fn parse_value(input: Pair<Rule>) -> Result<Value> {
     })
 }
 
-fn parse_string(input: Pair<Rule>) -> Result<&str> {
+fn parse_string(input: Pair<'_, Rule>) -> Result<&str> {
     debug_assert_eq!(input.as_rule(), Rule::string);
     Ok(input.into_inner().next().unwrap().as_str())
 }
@@ -156,6 +160,9 @@ fn parse_kind(input: Pair<Rule>) -> Result<Kind> {
         Rule::t_byte => Kind::Byte,
         Rule::t_long => Kind::Long,
         Rule::t_string => Kind::String,
+        Rule::array => Kind::Array,
+        Rule::star => Kind::Star,
+        Rule::struct_decl => Kind::Struct(parse_types(input.into_inner().next().unwrap())?),
         Rule::prop => Kind::Prop(parse_prop(input)?),
         _ => panic!("Unexpected kind"),
     })
@@ -202,27 +209,78 @@ fn parse_simple_trace(input: Pair<Rule>) -> Result<Trace> {
     Ok(Trace::Signal { name, units })
 }
 
-fn parse_value_section(input: Pair<Rule>) -> Result<Vec<SignalValues>> {
+/// Determines how parenthesized values are interpreted from the declared types of signals.
+struct Composites<'a> {
+    /// Maps the name of each complex or struct type to `true` if it is complex.
+    types: HashMap<&'a str, bool>,
+    /// Maps the name of each signal to the name of its type.
+    traces: HashMap<&'a str, &'a str>,
+}
+
+impl<'a> Composites<'a> {
+    fn new(types: &[TypeDef<'a>], traces: &[Trace<'a>]) -> Self {
+        let types = types
+            .iter()
+            .filter_map(|ty| {
+                ty.kinds.iter().find_map(|kind| match kind {
+                    Kind::Complex => Some((ty.name, true)),
+                    Kind::Struct(_) => Some((ty.name, false)),
+                    _ => None,
+                })
+            })
+            .collect();
+        let traces = traces
+            .iter()
+            .filter_map(|trace| match trace {
+                Trace::Signal { name, units } => Some((*name, *units)),
+                Trace::Group { .. } => None,
+            })
+            .collect();
+        Self { types, traces }
+    }
+
+    /// Returns whether the values of `signal` are complex (`Some(true)`) or structs
+    /// (`Some(false)`), or `None` if its type is not declared as either.
+    ///
+    /// The type is given by `sigtype` if present, or by the signal's trace otherwise.
+    fn is_complex(&self, signal: &str, sigtype: Option<&str>) -> Option<bool> {
+        let ty = sigtype.or_else(|| self.traces.get(signal).copied())?;
+        self.types.get(ty).copied()
+    }
+}
+
+fn parse_value_section<'a>(
+    input: Pair<'a, Rule>,
+    composites: &Composites,
+) -> Result<Vec<SignalValues<'a>>> {
     debug_assert_eq!(input.as_rule(), Rule::value_section);
     let pairs = input.into_inner();
-    pairs.map(parse_signal_value).collect::<Result<Vec<_>>>()
+    pairs
+        .map(|pair| parse_signal_value(pair, composites))
+        .collect::<Result<Vec<_>>>()
 }
 
-fn parse_signal_value(input: Pair<Rule>) -> Result<SignalValues> {
+fn parse_signal_value<'a>(
+    input: Pair<'a, Rule>,
+    composites: &Composites,
+) -> Result<SignalValues<'a>> {
     debug_assert_eq!(input.as_rule(), Rule::signal_value);
     let input = input.into_inner().next().unwrap();
     Ok(match input.as_rule() {
-        Rule::signal_value_simple => parse_signal_value_simple(input)?,
-        Rule::signal_value_typed => parse_signal_value_typed(input)?,
+        Rule::signal_value_simple => parse_signal_value_simple(input, composites)?,
+        Rule::signal_value_typed => parse_signal_value_typed(input, composites)?,
         r => panic!("Unexpected signal value {:?}", r),
     })
 }
 
-fn parse_signal_value_simple(input: Pair<Rule>) -> Result<SignalValues> {
+fn parse_signal_value_simple<'a>(
+    input: Pair<'a, Rule>,
+    composites: &Composites,
+) -> Result<SignalValues<'a>> {
     debug_assert_eq!(input.as_rule(), Rule::signal_value_simple);
     let mut input = input.into_inner();
     let signal = parse_string(input.next().unwrap())?;
-    let values = parse_numbers(input.next().unwrap())?;
+    let values = parse_numbers(input.next().unwrap(), composites.is_complex(signal, None))?;
     Ok(SignalValues {
         signal,
         sigtype: None,
@@ -230,12 +288,18 @@ fn parse_signal_value_simple(input: Pair<Rule>) -> Result<SignalValues> {
     })
 }
 
-fn parse_signal_value_typed(input: Pair<Rule>) -> Result<SignalValues> {
+fn parse_signal_value_typed<'a>(
+    input: Pair<'a, Rule>,
+    composites: &Composites,
+) -> Result<SignalValues<'a>> {
     debug_assert_eq!(input.as_rule(), Rule::signal_value_typed);
     let mut input = input.into_inner();
     let signal = parse_string(input.next().unwrap())?;
     let sigtype = Some(parse_string(input.next().unwrap())?);
-    let values = parse_numbers(input.next().unwrap())?;
+    let values = parse_numbers(
+        input.next().unwrap(),
+        composites.is_complex(signal, sigtype),
+    )?;
     Ok(SignalValues {
         signal,
         sigtype,
@@ -243,10 +307,15 @@ fn parse_signal_value_typed(input: Pair<Rule>) -> Result<SignalValues> {
     })
 }
 
-fn parse_numbers(input: Pair<Rule>) -> Result<Values> {
+/// Parses the values of a signal.
+///
+/// `complex` is the interpretation of parenthesized values given by the signal's type;
+/// see [`parse_composite_numbers`].
+fn parse_numbers(input: Pair<Rule>, complex: Option<bool>) -> Result<Values> {
     Ok(match input.as_rule() {
         Rule::simple_numbers => Values::Real(parse_simple_numbers(input)?),
-        Rule::composite_numbers => Values::Complex(parse_complex_numbers(input)?),
+        Rule::composite_numbers => parse_composite_numbers(input, complex)?,
+        Rule::mixed_struct => Values::Mixed(parse_field_list(input.into_inner().next().unwrap())?),
         _ => panic!("Unexpected numbers type"),
     })
 }
@@ -254,24 +323,83 @@ fn parse_numbers(input: Pair<Rule>) -> Result<Values> {
 fn parse_simple_numbers(input: Pair<Rule>) -> Result<Vec<f64>> {
     debug_assert_eq!(input.as_rule(), Rule::simple_numbers);
     let pairs = input.into_inner();
-    pairs.map(parse_real).collect::<Result<Vec<_>>>()
+    pairs
+        .filter(|pair| pair.as_rule() != Rule::prop)
+        .map(parse_number)
+        .collect::<Result<Vec<_>>>()
+}
+
+fn parse_number(input: Pair<Rule>) -> Result<f64> {
+    Ok(match input.as_rule() {
+        Rule::real => parse_real(input)?,
+        Rule::integer => parse_integer(input)? as f64,
+        Rule::nan => f64::NAN,
+        r => panic!("Unexpected number {:?}", r),
+    })
 }
 
-fn parse_complex_numbers(input: Pair<Rule>) -> Result<Vec<Complex64>> {
+/// Parses parenthesized numbers.
+///
+/// If `complex` is `Some(true)`, each entry must be a pair of numbers forming a complex number.
+/// If it is `Some(false)`, each entry contains the fields of a struct, with nested numbers
+/// flattened in order. If the type is not declared, pairs of numbers are interpreted as
+/// complex numbers and anything else as structs.
+fn parse_composite_numbers(input: Pair<Rule>, complex: Option<bool>) -> Result<Values> {
     debug_assert_eq!(input.as_rule(), Rule::composite_numbers);
-    let pairs = input.into_inner().next().unwrap().into_inner();
-    let numbers = pairs
-        .map(parse_simple_numbers)
-        .collect::<Result<Vec<_>>>()?;
-    Ok(numbers
-        .iter()
+    let mut nested = false;
+    let numbers = input
+        .into_inner()
         .map(|number| {
-            assert_eq!(
-                number.len(),
-                2,
-                "complex numbers should have exactly two entries"
-            );
-            Complex64::new(number[0], number[1])
+            let mut flat = Vec::new();
+            flatten_composite_number(number, &mut flat, &mut nested)?;
+            Ok(flat)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let pairs = !nested && numbers.iter().all(|number| number.len() == 2);
+    if complex.unwrap_or(pairs) {
+        if !pairs {
+            bail!("complex values must have a real and an imaginary part");
+        }
+        Ok(Values::Complex(
+            numbers
+                .iter()
+                .map(|number| Complex64::new(number[0], number[1]))
+                .collect(),
+        ))
+    } else {
+        Ok(Values::Struct(numbers))
+    }
+}
+
+fn parse_field_list(input: Pair<Rule>) -> Result<Vec<Field>> {
+    debug_assert_eq!(input.as_rule(), Rule::field_list);
+    input
+        .into_inner()
+        .map(|pair| {
+            Ok(match pair.as_rule() {
+                Rule::string => Field::Str(parse_string(pair)?.to_string()),
+                Rule::field_list => Field::List(parse_field_list(pair)?),
+                _ => Field::Real(parse_number(pair)?),
+            })
         })
-        .collect())
+        .collect()
+}
+
+fn flatten_composite_number(
+    input: Pair<Rule>,
+    out: &mut Vec<f64>,
+    nested: &mut bool,
+) -> Result<()> {
+    debug_assert_eq!(input.as_rule(), Rule::composite_number);
+    for pair in input.into_inner() {
+        match pair.as_rule() {
+            Rule::simple_numbers => out.extend(parse_simple_numbers(pair)?),
+            Rule::composite_number => {
+                *nested = true;
+                flatten_composite_number(pair, out, nested)?;
+            }
+            _ => {}
+        }
+    }
+    Ok(())
 }
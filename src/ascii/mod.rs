@@ -1,5 +1,6 @@
 pub mod ast;
 pub mod frontend;
+pub mod owned;
 
 pub use frontend::parse;
 
@@ -0,0 +1,273 @@
+//! Owned counterparts of the types in [`crate::ascii::ast`].
+//!
+//! The parsed AST borrows its strings from the input file. These types own their
+//! strings instead, so they can outlive the input, for example when deserialized
+//! from a cache. Convert between the two with [`From`]:
+//! an owned AST can be borrowed as an [`ast::PsfAst`] and passed to the analysis readers.
+
+use super::ast::{self, Values};
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PsfAst {
+    pub header: Header,
+    pub types: Vec<TypeDef>,
+    pub sweeps: Vec<Sweep>,
+    pub traces: Vec<Trace>,
+    pub values: Vec<SignalValues>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Header {
+    pub values: Vec<NamedValue>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TypeDef {
+    pub name: String,
+    pub kinds: Vec<Kind>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NamedValue {
+    pub name: String,
+    pub value: Value,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Value {
+    Int(i64),
+    Real(f64),
+    Str(String),
+    NaN,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Sweep {
+    pub name: String,
+    pub sweep_type: String,
+    pub kinds: Vec<Kind>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Kind {
+    Float,
+    Double,
+    Complex,
+    Int,
+    Byte,
+    Long,
+    String,
+    Array,
+    Struct(Vec<TypeDef>),
+    Prop(Prop),
+    Star,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Trace {
+    Group { name: String, count: i64 },
+    Signal { name: String, units: String },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Prop {
+    pub values: Vec<NamedValue>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SignalValues {
+    pub signal: String,
+    pub sigtype: Option<String>,
+    pub values: Values,
+}
+
+impl From<&ast::PsfAst<'_>> for PsfAst {
+    fn from(value: &ast::PsfAst<'_>) -> Self {
+        Self {
+            header: Header {
+                values: value.header.values.iter().map(Into::into).collect(),
+            },
+            types: value.types.iter().map(Into::into).collect(),
+            sweeps: value.sweeps.iter().map(Into::into).collect(),
+            traces: value.traces.iter().map(Into::into).collect(),
+            values: value.values.iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl<'a> From<&'a PsfAst> for ast::PsfAst<'a> {
+    fn from(value: &'a PsfAst) -> Self {
+        Self {
+            header: ast::Header {
+                values: value.header.values.iter().map(Into::into).collect(),
+            },
+            types: value.types.iter().map(Into::into).collect(),
+            sweeps: value.sweeps.iter().map(Into::into).collect(),
+            traces: value.traces.iter().map(Into::into).collect(),
+            values: value.values.iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<&ast::TypeDef<'_>> for TypeDef {
+    fn from(value: &ast::TypeDef<'_>) -> Self {
+        Self {
+            name: value.name.to_string(),
+            kinds: value.kinds.iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl<'a> From<&'a TypeDef> for ast::TypeDef<'a> {
+    fn from(value: &'a TypeDef) -> Self {
+        Self {
+            name: &value.name,
+            kinds: value.kinds.iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<&ast::NamedValue<'_>> for NamedValue {
+    fn from(value: &ast::NamedValue<'_>) -> Self {
+        Self {
+            name: value.name.to_string(),
+            value: match value.value {
+                ast::Value::Int(x) => Value::Int(x),
+                ast::Value::Real(x) => Value::Real(x),
+                ast::Value::Str(s) => Value::Str(s.to_string()),
+                ast::Value::NaN => Value::NaN,
+            },
+        }
+    }
+}
+
+impl<'a> From<&'a NamedValue> for ast::NamedValue<'a> {
+    fn from(value: &'a NamedValue) -> Self {
+        Self {
+            name: &value.name,
+            value: match value.value {
+                Value::Int(x) => ast::Value::Int(x),
+                Value::Real(x) => ast::Value::Real(x),
+                Value::Str(ref s) => ast::Value::Str(s),
+                Value::NaN => ast::Value::NaN,
+            },
+        }
+    }
+}
+
+impl From<&ast::Sweep<'_>> for Sweep {
+    fn from(value: &ast::Sweep<'_>) -> Self {
+        Self {
+            name: value.name.to_string(),
+            sweep_type: value.sweep_type.to_string(),
+            kinds: value.kinds.iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl<'a> From<&'a Sweep> for ast::Sweep<'a> {
+    fn from(value: &'a Sweep) -> Self {
+        Self {
+            name: &value.name,
+            sweep_type: &value.sweep_type,
+            kinds: value.kinds.iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<&ast::Kind<'_>> for Kind {
+    fn from(value: &ast::Kind<'_>) -> Self {
+        match value {
+            ast::Kind::Float => Self::Float,
+            ast::Kind::Double => Self::Double,
+            ast::Kind::Complex => Self::Complex,
+            ast::Kind::Int => Self::Int,
+            ast::Kind::Byte => Self::Byte,
+            ast::Kind::Long => Self::Long,
+            ast::Kind::String => Self::String,
+            ast::Kind::Array => Self::Array,
+            ast::Kind::Struct(types) => Self::Struct(types.iter().map(Into::into).collect()),
+            ast::Kind::Prop(prop) => Self::Prop(Prop {
+                values: prop.values.iter().map(Into::into).collect(),
+            }),
+            ast::Kind::Star => Self::Star,
+        }
+    }
+}
+
+impl<'a> From<&'a Kind> for ast::Kind<'a> {
+    fn from(value: &'a Kind) -> Self {
+        match value {
+            Kind::Float => Self::Float,
+            Kind::Double => Self::Double,
+            Kind::Complex => Self::Complex,
+            Kind::Int => Self::Int,
+            Kind::Byte => Self::Byte,
+            Kind::Long => Self::Long,
+            Kind::String => Self::String,
+            Kind::Array => Self::Array,
+            Kind::Struct(types) => Self::Struct(types.iter().map(Into::into).collect()),
+            Kind::Prop(prop) => Self::Prop(ast::Prop {
+                values: prop.values.iter().map(Into::into).collect(),
+            }),
+            Kind::Star => Self::Star,
+        }
+    }
+}
+
+impl From<&ast::Trace<'_>> for Trace {
+    fn from(value: &ast::Trace<'_>) -> Self {
+        match *value {
+            ast::Trace::Group { name, count } => Self::Group {
+                name: name.to_string(),
+                count,
+            },
+            ast::Trace::Signal { name, units } => Self::Signal {
+                name: name.to_string(),
+                units: units.to_string(),
+            },
+        }
+    }
+}
+
+impl<'a> From<&'a Trace> for ast::Trace<'a> {
+    fn from(value: &'a Trace) -> Self {
+        match value {
+            Trace::Group { name, count } => Self::Group {
+                name,
+                count: *count,
+            },
+            Trace::Signal { name, units } => Self::Signal { name, units },
+        }
+    }
+}
+
+impl From<&ast::SignalValues<'_>> for SignalValues {
+    fn from(value: &ast::SignalValues<'_>) -> Self {
+        Self {
+            signal: value.signal.to_string(),
+            sigtype: value.sigtype.map(str::to_string),
+            values: value.values.clone(),
+        }
+    }
+}
+
+impl<'a> From<&'a SignalValues> for ast::SignalValues<'a> {
+    fn from(value: &'a SignalValues) -> Self {
+        Self {
+            signal: &value.signal,
+            sigtype: value.sigtype.as_deref(),
+            values: value.values.clone(),
+        }
+    }
+}
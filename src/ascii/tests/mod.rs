@@ -87,7 +87,7 @@ pub(crate) static AC_EXAMPLE_PSF: &str = include_str!(concat!(
     "/examples/frequencySweep.ac"
 ));
 
-static DC_EXAMPLE1_PSF: &str =
+pub(crate) static DC_EXAMPLE1_PSF: &str =
     include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/examples/dc1.dc"));
 
 static DC_EXAMPLE2_PSF: &str =
@@ -1,7 +1,11 @@
+use anyhow::bail;
 use num::complex::Complex64;
 use std::collections::HashMap;
 
+use crate::Result;
+
 #[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct PsfAst<'a> {
     pub header: Header<'a>,
     pub types: Types<'a>,
@@ -14,25 +18,66 @@ impl<'a> PsfAst<'a> {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Resolves the units of a signal from its own properties or those of its type.
+    pub fn units(&self, signal: &SignalRef<'a>) -> Option<&'a str> {
+        let units = match signal.properties.get("units") {
+            Some(units) => units,
+            None => self
+                .types
+                .types
+                .get(&signal.type_id)?
+                .properties
+                .get("units")?,
+        };
+        match units {
+            Value::Str(units) => Some(units),
+            _ => None,
+        }
+    }
+
+    /// Resolves the units of every trace and sweep, keyed by signal name.
+    pub fn signal_units(&self) -> HashMap<String, String> {
+        let traces = self.traces.iter().flat_map(|trace| trace.signals());
+        traces
+            .chain(self.sweeps.iter())
+            .filter_map(|sig| Some((sig.name.to_string(), self.units(sig)?.to_string())))
+            .collect()
+    }
 }
 
 #[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Header<'a> {
     pub values: HashMap<&'a str, Value<'a>>,
 }
 
+impl Header<'_> {
+    /// Formats every header value as a string, keyed by name.
+    pub fn to_strings(&self) -> HashMap<String, String> {
+        self.values
+            .iter()
+            .map(|(name, v)| (name.to_string(), v.to_string()))
+            .collect()
+    }
+}
+
 #[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Types<'a> {
     pub types: HashMap<TypeId, TypeDef<'a>>,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TypeId(pub u32);
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TraceId(pub u32);
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GroupId(pub u32);
 
 impl From<GroupId> for TraceId {
@@ -42,6 +87,7 @@ impl From<GroupId> for TraceId {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct TypeDef<'a> {
     pub id: TypeId,
     pub name: &'a str,
@@ -50,12 +96,14 @@ pub struct TypeDef<'a> {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct NamedValue<'a> {
     pub name: &'a str,
     pub value: Value<'a>,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u32)]
 pub enum DataType {
     Int8 = 1,
@@ -81,6 +129,7 @@ impl DataType {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Value<'a> {
     Int(i64),
     Real(f64),
@@ -112,7 +161,20 @@ impl<'a> Value<'a> {
     }
 }
 
+impl std::fmt::Display for Value<'_> {
+    /// Formats the value as it would appear in a PSF header, without quotes.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Int(x) => write!(f, "{x}"),
+            Self::Real(x) => write!(f, "{x}"),
+            Self::Str(s) => f.write_str(s),
+            Self::NaN => f.write_str("NaN"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Sweep<'a> {
     pub name: &'a str,
     pub sweep_type: &'a str,
@@ -120,6 +182,7 @@ pub struct Sweep<'a> {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Kind<'a> {
     Float,
     Double,
@@ -135,20 +198,21 @@ pub enum Kind<'a> {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Trace<'a> {
     Group(TraceGroup<'a>),
     Signal(SignalRef<'a>),
 }
 
 impl<'a> Trace<'a> {
-    pub fn group(&self) -> &TraceGroup {
+    pub fn group(&self) -> &TraceGroup<'_> {
         match self {
             Self::Group(g) => g,
             _ => panic!("Cannot unwrap signal as group"),
         }
     }
 
-    pub fn signal(&self) -> &SignalRef {
+    pub fn signal(&self) -> &SignalRef<'_> {
         match self {
             Self::Signal(s) => s,
             _ => panic!("Cannot unwrap group trace as signal"),
@@ -164,6 +228,7 @@ impl<'a> Trace<'a> {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct TraceGroup<'a> {
     pub name: &'a str,
     pub count: u32,
@@ -172,11 +237,22 @@ pub struct TraceGroup<'a> {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Properties<'a> {
     pub values: Vec<NamedValue<'a>>,
 }
 
+impl<'a> Properties<'a> {
+    pub fn get(&self, name: &str) -> Option<&Value<'a>> {
+        self.values
+            .iter()
+            .find(|v| v.name == name)
+            .map(|v| &v.value)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct SignalRef<'a> {
     pub id: TraceId,
     pub name: &'a str,
@@ -185,16 +261,45 @@ pub struct SignalRef<'a> {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Prop<'a> {
     pub values: Vec<NamedValue<'a>>,
 }
 
 #[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SignalValues {
     pub values: HashMap<TraceId, Values>,
 }
 
+impl SignalValues {
+    /// Removes the values of `signal`, failing if they are missing or complex.
+    pub fn take_real(&mut self, signal: &SignalRef) -> Result<Vec<f64>> {
+        match self.values.remove(&signal.id) {
+            Some(Values::Real(v)) => Ok(v),
+            Some(Values::Complex(_)) => bail!(
+                "expected real values for signal `{}`; found complex",
+                signal.name
+            ),
+            None => bail!("missing values for signal `{}`", signal.name),
+        }
+    }
+
+    /// Removes the values of `signal`, failing if they are missing or real.
+    pub fn take_complex(&mut self, signal: &SignalRef) -> Result<Vec<Complex64>> {
+        match self.values.remove(&signal.id) {
+            Some(Values::Complex(v)) => Ok(v),
+            Some(Values::Real(_)) => bail!(
+                "expected complex values for signal `{}`; found real",
+                signal.name
+            ),
+            None => bail!("missing values for signal `{}`", signal.name),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Values {
     Complex(Vec<Complex64>),
     Real(Vec<f64>),
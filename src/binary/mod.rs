@@ -1,18 +1,21 @@
+use anyhow::{anyhow, bail};
 use num::complex::Complex64;
 use std::collections::HashMap;
 
 use self::ast::*;
 
 pub mod ast;
+pub mod owned;
+pub mod tail;
 
 #[cfg(test)]
 pub(crate) mod tests;
 
 use crate::Result;
 
-pub fn parse(input: &[u8]) -> Result<PsfAst> {
+pub fn parse(input: &[u8]) -> Result<PsfAst<'_>> {
     let mut parser = PsfParser::new(input);
-    parser.parse();
+    parser.parse()?;
     Ok(parser.into_inner())
 }
 
@@ -36,13 +39,16 @@ impl<'a> PsfParser<'a> {
         }
     }
 
-    pub fn parse(&mut self) {
+    /// Parses the whole file.
+    ///
+    /// Fails if a section contains an unexpected block.
+    pub fn parse(&mut self) -> Result<()> {
         self.parse_toc();
-        self.parse_header();
-        self.parse_types();
-        self.parse_sweeps();
-        self.parse_traces();
-        self.parse_values();
+        self.parse_header()?;
+        self.parse_types()?;
+        self.parse_sweeps()?;
+        self.parse_traces()?;
+        self.parse_values()
     }
 
     #[inline]
@@ -84,7 +90,7 @@ impl<'a> PsfParser<'a> {
         v.int()
     }
 
-    fn parse_values(&mut self) {
+    fn parse_values(&mut self) -> Result<()> {
         let entry = self.toc().section(SectionKind::Value);
         let (data, _eofs) = parse_int(&self.data[entry.start + 4..]);
 
@@ -101,24 +107,15 @@ impl<'a> PsfParser<'a> {
                 }
             }
 
-            let (data, block_t) = parse_int(data);
-            assert_eq!(block_t, 20);
-            let data = parse_zero_pad(data);
+            let data =
+                parse_value_preamble(data)?.ok_or_else(|| anyhow!("truncated value section"))?;
 
             let mut data = data;
             let mut count = 0;
             while count < sweep_points {
-                let block_init;
-                let mut block_t;
-                (data, block_t) = parse_int(data);
-                if block_t == 20 {
-                    data = parse_zero_pad(data);
-                    (data, block_t) = parse_int(data);
-                }
-                assert_eq!(block_t, 16);
-                (data, block_init) = parse_int(data);
-                let _window_left = block_init >> 16;
-                let window_count = block_init & 0xffff;
+                let window;
+                (data, window) = parse_window(data, window_size as u32, num_traces as u32)?
+                    .ok_or_else(|| anyhow!("truncated value window"))?;
 
                 let swp_sig = &self.ast.sweeps[0];
                 let swp_name = swp_sig.name;
@@ -128,25 +125,12 @@ impl<'a> PsfParser<'a> {
                     .values
                     .entry(swp_sig.id)
                     .or_insert(Values::Real(vec![]));
-                let swp_vec = swp_vec.real_mut();
-
-                for _ in 0..window_count {
-                    let v;
-                    (data, v) = parse_float(data);
-                    swp_vec.push(v);
-                }
+                swp_vec.real_mut().extend(window.sweep_values());
 
                 for group in self.ast.traces.iter() {
                     for sig in group.group().signals.iter() {
-                        let offset = self.offsets[&sig.id];
-                        let data_len = window_count * 8;
-                        let idx = if data_len > window_size as u32 {
-                            offset as usize
-                        } else {
-                            (offset + (window_size as u32 - data_len)) as usize
-                        };
                         let data_type = self.ast.types.types[&sig.type_id].data_type;
-                        let mut databuf = &data[idx..];
+                        let mut databuf = window.trace(self.offsets[&sig.id]);
 
                         assert_ne!(swp_name, sig.name);
 
@@ -159,7 +143,7 @@ impl<'a> PsfParser<'a> {
                                     .entry(sig.id)
                                     .or_insert(Values::Real(vec![]));
                                 let values = values.real_mut();
-                                for _ in 0..window_count {
+                                for _ in 0..window.count {
                                     let v = read_f64(&mut databuf);
                                     values.push(v);
                                 }
@@ -172,19 +156,18 @@ impl<'a> PsfParser<'a> {
                                     .entry(sig.id)
                                     .or_insert(Values::Complex(vec![]));
                                 let values = values.complex_mut();
-                                for _ in 0..window_count {
+                                for _ in 0..window.count {
                                     let real = read_f64(&mut databuf);
                                     let imag = read_f64(&mut databuf);
                                     values.push(Complex64::new(real, imag));
                                 }
                             }
-                            _ => panic!("Unsupported data type: {data_type:?}"),
+                            _ => bail!("unsupported data type {data_type:?}"),
                         };
                     }
                 }
 
-                data = &data[(num_traces * window_size) as usize..];
-                count += window_count as i64;
+                count += window.count as i64;
             }
         } else {
             let sweep_points = self.sweep_points();
@@ -238,28 +221,34 @@ impl<'a> PsfParser<'a> {
                                 let imag = read_f64(&mut data);
                                 values.push(Complex64::new(real, imag));
                             }
-                            _ => panic!("Unsupported data type: {data_type:?}"),
+                            _ => bail!("unsupported data type {data_type:?}"),
                         };
                     }
                 }
             }
         }
+
+        Ok(())
     }
 
-    fn parse_types(&mut self) {
-        self.ast.types = parse_types(self.data, &self.toc().section(SectionKind::Type));
+    fn parse_types(&mut self) -> Result<()> {
+        self.ast.types = parse_types(self.data, &self.toc().section(SectionKind::Type))?;
+        Ok(())
     }
 
-    fn parse_sweeps(&mut self) {
-        self.ast.sweeps = parse_sweeps(self.data, &self.toc().section(SectionKind::Sweep));
+    fn parse_sweeps(&mut self) -> Result<()> {
+        self.ast.sweeps = parse_sweeps(self.data, &self.toc().section(SectionKind::Sweep))?;
+        Ok(())
     }
 
-    fn parse_traces(&mut self) {
-        self.ast.traces = parse_traces(self.data, &self.toc().section(SectionKind::Trace));
+    fn parse_traces(&mut self) -> Result<()> {
+        self.ast.traces = parse_traces(self.data, &self.toc().section(SectionKind::Trace))?;
+        Ok(())
     }
 
-    fn parse_header(&mut self) {
-        self.ast.header = parse_header(self.data, &self.toc().section(SectionKind::Header));
+    fn parse_header(&mut self) -> Result<()> {
+        self.ast.header = parse_header(self.data, &self.toc().section(SectionKind::Header))?;
+        Ok(())
     }
 }
 
@@ -292,12 +281,144 @@ fn parse_toc(data: &[u8]) -> Toc {
     toc
 }
 
-fn parse_zero_pad(data: &[u8]) -> &[u8] {
-    let (data, len) = parse_int(data);
-    &data[len as usize..]
+/// Locates the sections of a file by walking the section headers from the start of the file,
+/// rather than reading the table of contents at its end.
+///
+/// Simulators write the table of contents last, so this is the only way to read
+/// a file that is still being written. The value section is assumed to extend to
+/// the end of the available data.
+///
+/// Returns `None` if the header, type, sweep and trace sections are not all complete.
+fn scan_toc(data: &[u8]) -> Option<Toc> {
+    const SECTION_MARKER: u32 = 21;
+
+    let mut toc = Toc::with_capacity(5);
+    let mut start = 4;
+    for kind in [
+        SectionKind::Header,
+        SectionKind::Type,
+        SectionKind::Sweep,
+        SectionKind::Trace,
+    ] {
+        let section = data.get(start..start + 8)?;
+        if peek_u32(section) != SECTION_MARKER {
+            return None;
+        }
+        let end = peek_u32(&section[4..]) as usize;
+        if end <= start || end > data.len() {
+            return None;
+        }
+        toc.data.insert(kind, TocEntry { start, end });
+        start = end;
+    }
+    toc.data.insert(
+        SectionKind::Value,
+        TocEntry {
+            start,
+            end: data.len(),
+        },
+    );
+
+    Some(toc)
+}
+
+/// Fails unless `block_t` is the `expected` block type.
+fn expect_block(block_t: u32, expected: u32) -> Result<()> {
+    if block_t != expected {
+        bail!("expected block type {expected}; found {block_t}");
+    }
+    Ok(())
 }
 
-fn parse_sweeps<'a>(file: &'a [u8], entry: &TocEntry) -> Vec<SignalRef<'a>> {
+/// Skips the zero padding at the start of a windowed value section.
+///
+/// Returns `None` if `data` ends before the padding does.
+fn parse_value_preamble(data: &[u8]) -> Result<Option<&[u8]>> {
+    let Some((data, block_t)) = try_parse_int(data) else {
+        return Ok(None);
+    };
+    expect_block(block_t, 20)?;
+    Ok(try_parse_int(data).and_then(|(data, len)| data.get(len as usize..)))
+}
+
+/// A window of values in a windowed value section.
+#[derive(Debug, Clone, Copy)]
+struct ValueWindow<'a> {
+    /// The number of sweep points in this window.
+    count: u32,
+    window_size: u32,
+    sweep: &'a [u8],
+    traces: &'a [u8],
+}
+
+impl<'a> ValueWindow<'a> {
+    fn sweep_values(&self) -> impl Iterator<Item = f64> + 'a {
+        self.sweep
+            .chunks_exact(8)
+            .map(|mut chunk| read_f64(&mut chunk))
+    }
+
+    /// Returns the data for the trace stored at byte `offset` within each window.
+    fn trace(&self, offset: u32) -> &'a [u8] {
+        let data_len = self.count * 8;
+        let idx = if data_len > self.window_size {
+            offset as usize
+        } else {
+            (offset + (self.window_size - data_len)) as usize
+        };
+        &self.traces[idx..]
+    }
+}
+
+/// Parses the value window at the start of `data`.
+///
+/// Returns the data following the window, or `None` if `data` does not
+/// contain the complete window.
+/// Fails if the data is not a value window.
+fn parse_window(
+    data: &[u8],
+    window_size: u32,
+    num_traces: u32,
+) -> Result<Option<(&[u8], ValueWindow<'_>)>> {
+    let Some((mut data, mut block_t)) = try_parse_int(data) else {
+        return Ok(None);
+    };
+    if block_t == 20 {
+        let Some((rest, len)) = try_parse_int(data) else {
+            return Ok(None);
+        };
+        let Some((rest, next)) = rest.get(len as usize..).and_then(try_parse_int) else {
+            return Ok(None);
+        };
+        (data, block_t) = (rest, next);
+    }
+    expect_block(block_t, 16)?;
+    let Some((data, block_init)) = try_parse_int(data) else {
+        return Ok(None);
+    };
+    let _window_left = block_init >> 16;
+    let count = block_init & 0xffff;
+
+    let sweep_len = count as usize * 8;
+    let traces_len = (num_traces * window_size) as usize;
+    if data.len() < sweep_len + traces_len {
+        return Ok(None);
+    }
+    let (sweep, data) = data.split_at(sweep_len);
+    let (traces, data) = data.split_at(traces_len);
+
+    Ok(Some((
+        data,
+        ValueWindow {
+            count,
+            window_size,
+            sweep,
+            traces,
+        },
+    )))
+}
+
+fn parse_sweeps<'a>(file: &'a [u8], entry: &TocEntry) -> Result<Vec<SignalRef<'a>>> {
     let (_, eofs) = parse_int(&file[entry.start + 4..]);
 
     let mut data = &file[entry.start + 8..eofs as usize];
@@ -305,44 +426,44 @@ fn parse_sweeps<'a>(file: &'a [u8], entry: &TocEntry) -> Vec<SignalRef<'a>> {
 
     while data.len() > 4 {
         let (d, id) = parse_int(data);
-        assert_eq!(id, 16);
-        let r = parse_signal_ref(d);
+        expect_block(id, 16)?;
+        let r = parse_signal_ref(d)?;
         data = r.0;
         values.push(r.1);
     }
 
-    values
+    Ok(values)
 }
 
-fn parse_types<'a>(file: &'a [u8], entry: &TocEntry) -> Types<'a> {
+fn parse_types<'a>(file: &'a [u8], entry: &TocEntry) -> Result<Types<'a>> {
     let data = &file[entry.start + 8..];
     let (data, block_t) = parse_int(data);
-    assert_eq!(block_t, 22);
+    expect_block(block_t, 22)?;
     let (_, eofs) = parse_int(data);
     let mut data = &file[entry.start + 8 + 8..eofs as usize];
 
     let mut types = HashMap::new();
 
     while data.len() > 4 {
-        let r = parse_type_item(data);
+        let r = parse_type_item(data)?;
         data = r.0;
         types.insert(r.1.id, r.1);
     }
 
-    Types { types }
+    Ok(Types { types })
 }
 
-fn parse_type_item(data: &[u8]) -> (&[u8], TypeDef<'_>) {
+fn parse_type_item(data: &[u8]) -> Result<(&[u8], TypeDef<'_>)> {
     let (data, block_t) = parse_int(data);
-    assert_eq!(block_t, 16);
+    expect_block(block_t, 16)?;
 
     let (data, id) = parse_int(data);
     let (data, name) = parse_string(data);
     let (data, _array_t) = parse_int(data);
     let (data, data_type) = parse_int(data);
-    let (data, properties) = parse_properties(data);
+    let (data, properties) = parse_properties(data)?;
 
-    (
+    Ok((
         data,
         TypeDef {
             id: TypeId(id),
@@ -350,46 +471,46 @@ fn parse_type_item(data: &[u8]) -> (&[u8], TypeDef<'_>) {
             data_type: DataType::from_u32(data_type),
             properties,
         },
-    )
+    ))
 }
 
-fn parse_traces<'a>(file: &'a [u8], entry: &TocEntry) -> Vec<Trace<'a>> {
+fn parse_traces<'a>(file: &'a [u8], entry: &TocEntry) -> Result<Vec<Trace<'a>>> {
     let data = &file[entry.start + 8..];
     let (data, block_t) = parse_int(data);
-    assert_eq!(block_t, 22);
+    expect_block(block_t, 22)?;
     let (_, eofs) = parse_int(data);
     let mut data = &file[entry.start + 8 + 8..eofs as usize];
 
     let mut values = Vec::new();
 
     while data.len() > 4 {
-        let r = parse_trace_item(data);
+        let r = parse_trace_item(data)?;
         data = r.0;
         values.push(r.1);
     }
 
-    values
+    Ok(values)
 }
 
-fn parse_trace_item(data: &[u8]) -> (&[u8], Trace<'_>) {
+fn parse_trace_item(data: &[u8]) -> Result<(&[u8], Trace<'_>)> {
     let (data, block_t) = parse_int(data);
     match block_t {
         16 => {
             // DataTypeDef
-            let (data, signal) = parse_signal_ref(data);
-            (data, Trace::Signal(signal))
+            let (data, signal) = parse_signal_ref(data)?;
+            Ok((data, Trace::Signal(signal)))
         }
         17 => {
             // Group
-            let (data, group) = parse_group(data);
-            (data, Trace::Group(group))
+            let (data, group) = parse_group(data)?;
+            Ok((data, Trace::Group(group)))
         }
-        _ => panic!("Unexpected block type: {block_t}"),
+        _ => bail!("unexpected trace block type {block_t}"),
     }
 }
 
 // GroupDef
-fn parse_group(data: &[u8]) -> (&[u8], TraceGroup<'_>) {
+fn parse_group(data: &[u8]) -> Result<(&[u8], TraceGroup<'_>)> {
     let (data, id) = parse_int(data);
     let (data, name) = parse_string(data);
     let (mut data, count) = parse_int(data);
@@ -398,13 +519,13 @@ fn parse_group(data: &[u8]) -> (&[u8], TraceGroup<'_>) {
     for _ in 0..count {
         let r = parse_int(data);
         let block_t = r.1;
-        assert_eq!(block_t, 16);
-        let r = parse_signal_ref(r.0);
+        expect_block(block_t, 16)?;
+        let r = parse_signal_ref(r.0)?;
         data = r.0;
         signals.push(r.1);
     }
 
-    (
+    Ok((
         data,
         TraceGroup {
             name,
@@ -412,17 +533,17 @@ fn parse_group(data: &[u8]) -> (&[u8], TraceGroup<'_>) {
             id: GroupId(id),
             signals,
         },
-    )
+    ))
 }
 
 // data type ref
-fn parse_signal_ref(data: &[u8]) -> (&[u8], SignalRef<'_>) {
+fn parse_signal_ref(data: &[u8]) -> Result<(&[u8], SignalRef<'_>)> {
     let (data, id) = parse_int(data);
     let (data, name) = parse_string(data);
     let (data, type_id) = parse_int(data);
-    let (data, properties) = parse_properties(data);
+    let (data, properties) = parse_properties(data)?;
 
-    (
+    Ok((
         data,
         SignalRef {
             id: TraceId(id),
@@ -430,25 +551,25 @@ fn parse_signal_ref(data: &[u8]) -> (&[u8], SignalRef<'_>) {
             type_id: TypeId(type_id),
             properties,
         },
-    )
+    ))
 }
 
-fn parse_header<'a>(file: &'a [u8], entry: &TocEntry) -> Header<'a> {
+fn parse_header<'a>(file: &'a [u8], entry: &TocEntry) -> Result<Header<'a>> {
     let (_, eofs) = parse_int(&file[entry.start + 4..]);
 
     let mut data = &file[entry.start + 8..eofs as usize];
     let mut values = HashMap::new();
 
     while data.len() > 4 {
-        let r = parse_named_value(data);
+        let r = parse_named_value(data)?;
         data = r.0;
         values.insert(r.1.name, r.1.value);
     }
 
-    Header { values }
+    Ok(Header { values })
 }
 
-fn parse_properties(data: &[u8]) -> (&[u8], Properties) {
+fn parse_properties(data: &[u8]) -> Result<(&[u8], Properties<'_>)> {
     let mut data = data;
 
     let mut values = Vec::new();
@@ -460,14 +581,14 @@ fn parse_properties(data: &[u8]) -> (&[u8], Properties) {
         }
     } {
         let val;
-        (data, val) = parse_named_value(data);
+        (data, val) = parse_named_value(data)?;
         values.push(val);
     }
 
-    (data, Properties { values })
+    Ok((data, Properties { values }))
 }
 
-fn parse_named_value(data: &[u8]) -> (&[u8], NamedValue) {
+fn parse_named_value(data: &[u8]) -> Result<(&[u8], NamedValue<'_>)> {
     let (data, block_t) = parse_int(data);
     let (data, name) = parse_string(data);
 
@@ -484,16 +605,16 @@ fn parse_named_value(data: &[u8]) -> (&[u8], NamedValue) {
             let (data, i) = parse_float(data);
             (data, Value::Real(i))
         }
-        _ => panic!("Unexpected block type: {block_t}"),
+        _ => bail!("unexpected value block type {block_t}"),
     };
 
-    (data, NamedValue { name, value })
+    Ok((data, NamedValue { name, value }))
 }
 
 fn parse_string(mut data: &[u8]) -> (&[u8], &str) {
     let len = read_u32(&mut data) as usize;
     let s = std::str::from_utf8(&data[..len]).unwrap();
-    if len % 4 == 0 {
+    if len.is_multiple_of(4) {
         (&data[len..], s)
     } else {
         (&data[len + 4 - (len % 4)..], s)
@@ -505,6 +626,11 @@ fn parse_int(mut data: &[u8]) -> (&[u8], u32) {
     (data, val)
 }
 
+/// Like [`parse_int`], but returns `None` instead of panicking if `data` is too short.
+fn try_parse_int(data: &[u8]) -> Option<(&[u8], u32)> {
+    (data.len() >= 4).then(|| parse_int(data))
+}
+
 fn parse_float(mut data: &[u8]) -> (&[u8], f64) {
     let val = read_f64(&mut data);
     (data, val)
@@ -0,0 +1,265 @@
+//! Owned counterparts of the types in [`crate::binary::ast`].
+//!
+//! The parsed AST borrows its strings from the input file. These types own their
+//! strings instead, so they can outlive the input, for example when deserialized
+//! from a cache. Convert between the two with [`From`].
+//! Converting an owned AST back to an [`ast::PsfAst`] clones the signal values,
+//! since the analysis readers take ownership of them.
+
+use std::collections::HashMap;
+
+use super::ast::{self, DataType, GroupId, SignalValues, TraceId, TypeId};
+
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PsfAst {
+    pub header: Header,
+    pub types: Types,
+    pub sweeps: Vec<SignalRef>,
+    pub traces: Vec<Trace>,
+    pub values: SignalValues,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Header {
+    pub values: HashMap<String, Value>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Types {
+    pub types: HashMap<TypeId, TypeDef>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TypeDef {
+    pub id: TypeId,
+    pub name: String,
+    pub data_type: DataType,
+    pub properties: Properties,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NamedValue {
+    pub name: String,
+    pub value: Value,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Value {
+    Int(i64),
+    Real(f64),
+    Str(String),
+    NaN,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Trace {
+    Group(TraceGroup),
+    Signal(SignalRef),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TraceGroup {
+    pub name: String,
+    pub count: u32,
+    pub id: GroupId,
+    pub signals: Vec<SignalRef>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Properties {
+    pub values: Vec<NamedValue>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SignalRef {
+    pub id: TraceId,
+    pub name: String,
+    pub type_id: TypeId,
+    pub properties: Properties,
+}
+
+impl From<&ast::PsfAst<'_>> for PsfAst {
+    fn from(value: &ast::PsfAst<'_>) -> Self {
+        Self {
+            header: Header {
+                values: value
+                    .header
+                    .values
+                    .iter()
+                    .map(|(name, v)| (name.to_string(), v.into()))
+                    .collect(),
+            },
+            types: Types {
+                types: value
+                    .types
+                    .types
+                    .iter()
+                    .map(|(id, t)| (*id, t.into()))
+                    .collect(),
+            },
+            sweeps: value.sweeps.iter().map(Into::into).collect(),
+            traces: value.traces.iter().map(Into::into).collect(),
+            values: value.values.clone(),
+        }
+    }
+}
+
+impl<'a> From<&'a PsfAst> for ast::PsfAst<'a> {
+    fn from(value: &'a PsfAst) -> Self {
+        Self {
+            header: ast::Header {
+                values: value
+                    .header
+                    .values
+                    .iter()
+                    .map(|(name, v)| (name.as_str(), v.into()))
+                    .collect(),
+            },
+            types: ast::Types {
+                types: value
+                    .types
+                    .types
+                    .iter()
+                    .map(|(id, t)| (*id, t.into()))
+                    .collect(),
+            },
+            sweeps: value.sweeps.iter().map(Into::into).collect(),
+            traces: value.traces.iter().map(Into::into).collect(),
+            values: value.values.clone(),
+        }
+    }
+}
+
+impl From<&ast::TypeDef<'_>> for TypeDef {
+    fn from(value: &ast::TypeDef<'_>) -> Self {
+        Self {
+            id: value.id,
+            name: value.name.to_string(),
+            data_type: value.data_type,
+            properties: (&value.properties).into(),
+        }
+    }
+}
+
+impl<'a> From<&'a TypeDef> for ast::TypeDef<'a> {
+    fn from(value: &'a TypeDef) -> Self {
+        Self {
+            id: value.id,
+            name: &value.name,
+            data_type: value.data_type,
+            properties: (&value.properties).into(),
+        }
+    }
+}
+
+impl From<&ast::Value<'_>> for Value {
+    fn from(value: &ast::Value<'_>) -> Self {
+        match *value {
+            ast::Value::Int(x) => Self::Int(x),
+            ast::Value::Real(x) => Self::Real(x),
+            ast::Value::Str(s) => Self::Str(s.to_string()),
+            ast::Value::NaN => Self::NaN,
+        }
+    }
+}
+
+impl<'a> From<&'a Value> for ast::Value<'a> {
+    fn from(value: &'a Value) -> Self {
+        match value {
+            Value::Int(x) => Self::Int(*x),
+            Value::Real(x) => Self::Real(*x),
+            Value::Str(s) => Self::Str(s),
+            Value::NaN => Self::NaN,
+        }
+    }
+}
+
+impl From<&ast::Trace<'_>> for Trace {
+    fn from(value: &ast::Trace<'_>) -> Self {
+        match value {
+            ast::Trace::Group(group) => Self::Group(TraceGroup {
+                name: group.name.to_string(),
+                count: group.count,
+                id: group.id,
+                signals: group.signals.iter().map(Into::into).collect(),
+            }),
+            ast::Trace::Signal(sig) => Self::Signal(sig.into()),
+        }
+    }
+}
+
+impl<'a> From<&'a Trace> for ast::Trace<'a> {
+    fn from(value: &'a Trace) -> Self {
+        match value {
+            Trace::Group(group) => Self::Group(ast::TraceGroup {
+                name: &group.name,
+                count: group.count,
+                id: group.id,
+                signals: group.signals.iter().map(Into::into).collect(),
+            }),
+            Trace::Signal(sig) => Self::Signal(sig.into()),
+        }
+    }
+}
+
+impl From<&ast::Properties<'_>> for Properties {
+    fn from(value: &ast::Properties<'_>) -> Self {
+        Self {
+            values: value
+                .values
+                .iter()
+                .map(|v| NamedValue {
+                    name: v.name.to_string(),
+                    value: (&v.value).into(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl<'a> From<&'a Properties> for ast::Properties<'a> {
+    fn from(value: &'a Properties) -> Self {
+        Self {
+            values: value
+                .values
+                .iter()
+                .map(|v| ast::NamedValue {
+                    name: &v.name,
+                    value: (&v.value).into(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<&ast::SignalRef<'_>> for SignalRef {
+    fn from(value: &ast::SignalRef<'_>) -> Self {
+        Self {
+            id: value.id,
+            name: value.name.to_string(),
+            type_id: value.type_id,
+            properties: (&value.properties).into(),
+        }
+    }
+}
+
+impl<'a> From<&'a SignalRef> for ast::SignalRef<'a> {
+    fn from(value: &'a SignalRef) -> Self {
+        Self {
+            id: value.id,
+            name: &value.name,
+            type_id: value.type_id,
+            properties: (&value.properties).into(),
+        }
+    }
+}
@@ -0,0 +1,192 @@
+//! Incremental reading of binary PSF files that are still being written.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use anyhow::bail;
+
+use super::ast::DataType;
+use super::{parse_value_preamble, parse_window, read_f64, scan_toc, PsfParser, SectionKind};
+use crate::analysis::transient::TransientData;
+use crate::Result;
+
+/// Reads a windowed binary transient PSF file while the simulator is still writing it.
+///
+/// Each call to [`TailReader::poll`] reopens the file and returns the samples in any
+/// value windows completed since the previous call. Partially written windows are
+/// left for a later poll, and the table of contents at the end of the file is never read.
+#[derive(Debug, Clone)]
+pub struct TailReader {
+    path: PathBuf,
+    layout: Option<Layout>,
+    /// The byte offset of the next unread value window.
+    pos: u64,
+    /// The number of sweep points read so far.
+    samples: usize,
+}
+
+/// The parts of the file preamble needed to decode value windows.
+#[derive(Debug, Clone)]
+struct Layout {
+    sweep: String,
+    /// Signal names and their byte offsets within each window.
+    signals: Vec<(String, u32)>,
+    window_size: u32,
+    num_traces: u32,
+    sweep_points: usize,
+    units: HashMap<String, String>,
+}
+
+impl TailReader {
+    /// Creates a reader for the file at `path`.
+    ///
+    /// The file is not opened until the first call to [`TailReader::poll`],
+    /// so it need not exist yet.
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            layout: None,
+            pos: 0,
+            samples: 0,
+        }
+    }
+
+    #[inline]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The number of sweep points read so far.
+    #[inline]
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+
+    /// Returns `true` once all sweep points announced in the file header have been read.
+    pub fn is_complete(&self) -> bool {
+        self.layout
+            .as_ref()
+            .is_some_and(|layout| self.samples >= layout.sweep_points)
+    }
+
+    /// Reads any value windows completed since the last poll.
+    ///
+    /// The returned data contains only the new samples. It contains no samples
+    /// if nothing new has been written, including when the file preamble is incomplete.
+    ///
+    /// Fails if the file cannot be read, if it is not a windowed PSF file
+    /// containing only real signals, or if it contains an unexpected block.
+    pub fn poll(&mut self) -> Result<TransientData> {
+        let mut file = File::open(&self.path)?;
+        file.seek(SeekFrom::Start(self.pos))?;
+        let mut data = Vec::new();
+        file.read_to_end(&mut data)?;
+
+        let mut rest = data.as_slice();
+        if self.layout.is_none() {
+            let Some((layout, start)) = read_layout(&data)? else {
+                return Ok(TransientData {
+                    signals: HashMap::new(),
+                    time: "time".to_string(),
+                    units: HashMap::new(),
+                });
+            };
+            self.layout = Some(layout);
+            rest = &data[start..];
+        }
+        let layout = self.layout.as_ref().unwrap();
+
+        let mut sweep = Vec::new();
+        let mut signals = layout
+            .signals
+            .iter()
+            .map(|(name, _)| (name.clone(), Vec::new()))
+            .collect::<HashMap<_, _>>();
+
+        while self.samples < layout.sweep_points {
+            let Some((next, window)) = parse_window(rest, layout.window_size, layout.num_traces)?
+            else {
+                break;
+            };
+            sweep.extend(window.sweep_values());
+            for (name, offset) in layout.signals.iter() {
+                let mut databuf = window.trace(*offset);
+                let values = signals.get_mut(name).unwrap();
+                values.extend((0..window.count).map(|_| read_f64(&mut databuf)));
+            }
+            self.samples += window.count as usize;
+            rest = next;
+        }
+
+        self.pos += (data.len() - rest.len()) as u64;
+        signals.insert(layout.sweep.clone(), sweep);
+        Ok(TransientData {
+            signals,
+            time: layout.sweep.clone(),
+            units: layout.units.clone(),
+        })
+    }
+}
+
+/// Parses the preamble of a possibly incomplete file.
+///
+/// Returns the layout and the offset of the first value window,
+/// or `None` if the preamble has not been completely written.
+fn read_layout(data: &[u8]) -> Result<Option<(Layout, usize)>> {
+    let Some(toc) = scan_toc(data) else {
+        return Ok(None);
+    };
+    let value_start = toc.section(SectionKind::Value).start;
+
+    let mut parser = PsfParser::new(data);
+    parser.toc = Some(toc);
+    parser.parse_header()?;
+    parser.parse_types()?;
+    parser.parse_sweeps()?;
+    parser.parse_traces()?;
+
+    if !parser.windowed() {
+        bail!("only windowed PSF files can be read incrementally");
+    }
+    let Some(sweep) = parser.ast.sweeps.first() else {
+        bail!("PSF file has no sweep");
+    };
+
+    let window_size = parser.window_size() as u32;
+    let mut signals = Vec::new();
+    let mut ofs = 0;
+    for trace in parser.ast.traces.iter() {
+        for sig in trace.group().signals.iter() {
+            let data_type = parser.ast.types.types[&sig.type_id].data_type;
+            if data_type != DataType::Real {
+                bail!(
+                    "signal `{}` has unsupported data type {data_type:?}",
+                    sig.name
+                );
+            }
+            signals.push((sig.name.to_string(), ofs));
+            ofs += window_size;
+        }
+    }
+
+    let Some(windows) = data.get(value_start + 8..) else {
+        return Ok(None);
+    };
+    let Some(windows) = parse_value_preamble(windows)? else {
+        return Ok(None);
+    };
+
+    Ok(Some((
+        Layout {
+            sweep: sweep.name.to_string(),
+            signals,
+            window_size,
+            num_traces: parser.num_traces() as u32,
+            sweep_points: parser.sweep_points() as usize,
+            units: parser.ast.signal_units(),
+        },
+        data.len() - windows.len(),
+    )))
+}
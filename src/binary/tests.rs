@@ -24,14 +24,14 @@ pub(crate) static AC_ZOUT_PSFBIN: &[u8] =
 #[test]
 fn test_header() {
     let toc = parse_toc(TRAN_EXAMPLE_PSFBIN_1);
-    let header = parse_header(TRAN_EXAMPLE_PSFBIN_1, &toc.data[&SectionKind::Header]);
+    let header = parse_header(TRAN_EXAMPLE_PSFBIN_1, &toc.data[&SectionKind::Header]).unwrap();
     println!("Header: {:?}", header);
 }
 
 #[test]
 fn test_types() {
     let toc = parse_toc(TRAN_EXAMPLE_PSFBIN_1);
-    let types = parse_types(TRAN_EXAMPLE_PSFBIN_1, &toc.data[&SectionKind::Type]);
+    let types = parse_types(TRAN_EXAMPLE_PSFBIN_1, &toc.data[&SectionKind::Type]).unwrap();
     println!("Types: {:?}", types);
 }
 
@@ -39,7 +39,7 @@ fn test_types() {
 fn test_traces() {
     let toc = parse_toc(TRAN_EXAMPLE_PSFBIN_1);
     println!("ToC: {:?}", toc);
-    let traces = parse_traces(TRAN_EXAMPLE_PSFBIN_1, &toc.data[&SectionKind::Trace]);
+    let traces = parse_traces(TRAN_EXAMPLE_PSFBIN_1, &toc.data[&SectionKind::Trace]).unwrap();
     let expected = vec![Trace::Group(TraceGroup {
         name: "group",
         count: 3,
@@ -74,14 +74,14 @@ fn test_values() {
     let toc = parse_toc(TRAN_EXAMPLE_PSFBIN_1);
     println!("ToC: {:?}", toc);
     let mut parser = PsfParser::new(TRAN_EXAMPLE_PSFBIN_1);
-    parser.parse();
+    parser.parse().unwrap();
 }
 
 #[test]
 fn test_sweeps() {
     let toc = parse_toc(TRAN_EXAMPLE_PSFBIN_1);
     println!("ToC: {:?}", toc);
-    let sweeps = parse_sweeps(TRAN_EXAMPLE_PSFBIN_1, &toc.data[&SectionKind::Sweep]);
+    let sweeps = parse_sweeps(TRAN_EXAMPLE_PSFBIN_1, &toc.data[&SectionKind::Sweep]).unwrap();
     println!("Sweeps: {:?}", sweeps);
 }
 
@@ -135,3 +135,90 @@ fn parses_ac() {
     assert_eq!(data.signals.len(), 2);
     assert_eq!(data.freq.len(), 78);
 }
+
+#[test]
+fn tails_growing_file() {
+    use std::io::Write;
+
+    use super::tail::TailReader;
+
+    let expected = TransientData::from_binary(parse(VDIV_SIN_PSFBIN).unwrap());
+    // Omit the table of contents, which is only written once the simulation finishes.
+    let end = peek_u32(&VDIV_SIN_PSFBIN[VDIV_SIN_PSFBIN.len() - 4..]) as usize;
+    let contents = &VDIV_SIN_PSFBIN[..end];
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("tran.tran");
+    let mut reader = TailReader::new(&path);
+    let mut file = std::fs::File::create(&path).unwrap();
+
+    let mut signals = std::collections::HashMap::<String, Vec<f64>>::new();
+    let mut written = 0;
+    let mut polls_with_data = 0;
+    for chunk in contents.chunks(100_003) {
+        file.write_all(chunk).unwrap();
+        file.flush().unwrap();
+        written += chunk.len();
+
+        let data = reader.poll().unwrap();
+        let new = data.signal("time").map_or(0, Vec::len);
+        if new > 0 {
+            polls_with_data += 1;
+        }
+        for (name, values) in data.signals {
+            assert_eq!(values.len(), new);
+            signals.entry(name).or_default().extend(values);
+        }
+        assert_eq!(reader.is_complete(), written == contents.len());
+    }
+
+    assert!(polls_with_data > 1);
+    assert_eq!(reader.samples(), expected.signal("time").unwrap().len());
+    assert_eq!(reader.poll().unwrap().signal("time").unwrap().len(), 0);
+    assert_eq!(
+        TransientData {
+            signals,
+            time: "time".to_string(),
+            units: expected.units.clone(),
+        },
+        expected
+    );
+}
+
+#[test]
+fn tails_file_truncated_mid_window() {
+    use super::tail::TailReader;
+
+    let toc = scan_toc(VDIV_SIN_PSFBIN).unwrap();
+    let values = &VDIV_SIN_PSFBIN[toc.section(SectionKind::Value).start + 8..];
+    let windows = parse_value_preamble(values).unwrap().unwrap();
+    let start = VDIV_SIN_PSFBIN.len() - windows.len();
+    let header = parse(VDIV_SIN_PSFBIN).unwrap().header;
+    let window_size = header.values["PSF window size"].int() as u32;
+    let num_traces = header.values["PSF traces"].int() as u32;
+    let (rest, first) = parse_window(windows, window_size, num_traces)
+        .unwrap()
+        .unwrap();
+    let second = VDIV_SIN_PSFBIN.len() - rest.len();
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("tran.tran");
+    let mut reader = TailReader::new(&path);
+
+    // Cut the file inside the header of the second window, then inside its data.
+    std::fs::write(&path, &VDIV_SIN_PSFBIN[..second + 2]).unwrap();
+    let data = reader.poll().unwrap();
+    assert_eq!(data.signal("time").unwrap().len(), first.count as usize);
+    for end in [second + 6, second + 100] {
+        std::fs::write(&path, &VDIV_SIN_PSFBIN[..end]).unwrap();
+        assert_eq!(reader.poll().unwrap().signal("time").unwrap().len(), 0);
+    }
+    assert_eq!(reader.samples(), first.count as usize);
+
+    // Space reserved for a window but not yet written is an error, not a panic.
+    let mut contents = VDIV_SIN_PSFBIN[..start].to_vec();
+    contents.resize(start + 64, 0);
+    std::fs::write(&path, contents).unwrap();
+    let mut reader = TailReader::new(&path);
+    assert!(reader.poll().is_err());
+}
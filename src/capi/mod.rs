@@ -0,0 +1,366 @@
+//! C API, for use from C, C++ and other languages with a C foreign function interface.
+//!
+//! The declarations are in `include/psfparser.h`, which is generated by cbindgen.
+//! A file is opened with [`psf_open`] and must be freed with [`psf_close`].
+//! Functions that fail return `NULL` or a negative value, and [`psf_last_error`]
+//! describes the most recent failure on the calling thread.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::fmt::Display;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::ptr;
+
+use anyhow::{anyhow, bail, Context};
+use num::complex::Complex64;
+
+use crate::analysis::dc::DcData;
+use crate::results::{Analysis, PsfFile};
+use crate::Result;
+
+#[cfg(test)]
+mod tests;
+
+/// The C header declaring this API.
+#[cfg(test)]
+const HEADER: &str = include_str!(concat!(env!("OUT_DIR"), "/psfparser.h"));
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// An open PSF file containing a transient, AC or DC analysis.
+pub struct PsfHandle {
+    file: PsfFile,
+    header: HashMap<String, CString>,
+    /// The sweep variable, if any, followed by all other signals sorted by name.
+    names: Vec<CString>,
+}
+
+/// The values of a signal.
+enum Values<'a> {
+    Real(&'a [f64]),
+    Complex(&'a [Complex64]),
+}
+
+impl Values<'_> {
+    fn len(&self) -> usize {
+        match self {
+            Self::Real(values) => values.len(),
+            Self::Complex(values) => values.len(),
+        }
+    }
+}
+
+impl PsfHandle {
+    fn open(path: &Path) -> Result<Self> {
+        let file =
+            PsfFile::open(path).with_context(|| format!("failed to open `{}`", path.display()))?;
+        let (sweep, mut names) = match &file.analysis {
+            Analysis::Tran(data) => (Some(data.time.as_str()), keys(&data.signals)),
+            Analysis::Ac(data) => (Some("freq"), keys(&data.signals)),
+            Analysis::Dc(DcData::Sweep(data)) => (Some(data.param.0.as_str()), keys(&data.signals)),
+            Analysis::Dc(DcData::Op(data)) => (None, keys(&data.signals)),
+            _ => bail!(
+                "unsupported analysis type `{}`",
+                file.header["analysis type"]
+            ),
+        };
+        names.retain(|name| Some(*name) != sweep);
+        names.sort_unstable();
+        let names = sweep
+            .into_iter()
+            .chain(names)
+            .map(CString::new)
+            .collect::<std::result::Result<_, _>>()?;
+        let header = file
+            .header
+            .iter()
+            .map(|(name, value)| Ok((name.clone(), CString::new(value.as_str())?)))
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            file,
+            header,
+            names,
+        })
+    }
+
+    fn values(&self, name: &str) -> Result<Values<'_>> {
+        let values = match &self.file.analysis {
+            Analysis::Tran(data) => data.signal(name).map(|values| Values::Real(values)),
+            Analysis::Ac(data) if name == "freq" => Some(Values::Real(&data.freq)),
+            Analysis::Ac(data) => data.signal(name).map(|values| Values::Complex(values)),
+            Analysis::Dc(DcData::Sweep(data)) if name == data.param.0 => {
+                Some(Values::Real(&data.param.1))
+            }
+            Analysis::Dc(DcData::Sweep(data)) => {
+                data.signal(name).map(|values| Values::Real(values))
+            }
+            Analysis::Dc(DcData::Op(data)) => data
+                .signals
+                .get(name)
+                .map(|value| Values::Real(std::slice::from_ref(value))),
+            _ => None,
+        };
+        values.ok_or_else(|| anyhow!("no signal named `{name}`"))
+    }
+}
+
+fn keys<T>(signals: &HashMap<String, T>) -> Vec<&str> {
+    signals.keys().map(String::as_str).collect()
+}
+
+fn set_error(err: impl Display) {
+    // Messages never contain NUL bytes, but truncate at one just in case.
+    let msg = err.to_string();
+    let msg = msg.split('\0').next().unwrap_or_default();
+    let msg = CString::new(msg).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(msg));
+}
+
+/// Runs `f`, recording its error and returning `default` if it fails or panics.
+///
+/// Panics must not unwind into the caller, which is usually not written in Rust.
+fn catch<T>(default: T, f: impl FnOnce() -> Result<T>) -> T {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => value,
+        Ok(Err(err)) => {
+            set_error(format!("{err:#}"));
+            default
+        }
+        Err(payload) => {
+            let msg = payload
+                .downcast_ref::<&str>()
+                .copied()
+                .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+                .unwrap_or("unknown error");
+            set_error(format!("panicked: {msg}"));
+            default
+        }
+    }
+}
+
+/// Converts a handle passed by the caller to a reference.
+///
+/// # Safety
+///
+/// `handle` must be null or a pointer returned by [`psf_open`] that has not been closed.
+unsafe fn handle_ref<'a>(handle: *const PsfHandle) -> Result<&'a PsfHandle> {
+    handle.as_ref().ok_or_else(|| anyhow!("null PSF handle"))
+}
+
+/// Converts a string passed by the caller to a `&str`.
+///
+/// # Safety
+///
+/// `s` must be null or a valid NUL-terminated string.
+unsafe fn string<'a>(s: *const c_char) -> Result<&'a str> {
+    if s.is_null() {
+        bail!("null string argument");
+    }
+    Ok(CStr::from_ptr(s).to_str()?)
+}
+
+/// Gets the message describing the most recent error on the calling thread,
+/// or `NULL` if no error has occurred.
+///
+/// The message remains valid until the next error on the same thread.
+#[no_mangle]
+pub extern "C" fn psf_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |msg| msg.as_ptr())
+    })
+}
+
+/// Opens and parses the PSF file at `path`, which must contain a transient, AC or DC analysis.
+///
+/// Returns `NULL` on failure. The handle must be freed with [`psf_close`].
+///
+/// # Safety
+///
+/// `path` must be a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn psf_open(path: *const c_char) -> *mut PsfHandle {
+    catch(ptr::null_mut(), || {
+        let handle = PsfHandle::open(Path::new(string(path)?))?;
+        Ok(Box::into_raw(Box::new(handle)))
+    })
+}
+
+/// Frees a handle returned by [`psf_open`]. Does nothing if `handle` is `NULL`.
+///
+/// # Safety
+///
+/// `handle` must be null or a pointer returned by [`psf_open`] that has not been closed.
+#[no_mangle]
+pub unsafe extern "C" fn psf_close(handle: *mut PsfHandle) {
+    if !handle.is_null() {
+        drop(Box::from_raw(handle));
+    }
+}
+
+/// Gets the number of signals in the file, including the sweep variable.
+///
+/// # Safety
+///
+/// `handle` must be a pointer returned by [`psf_open`] that has not been closed.
+#[no_mangle]
+pub unsafe extern "C" fn psf_signal_count(handle: *const PsfHandle) -> usize {
+    catch(0, || Ok(handle_ref(handle)?.names.len()))
+}
+
+/// Gets the name of the signal at `index`, or `NULL` if `index` is out of range.
+///
+/// The sweep variable, if any, comes first, followed by the other signals sorted by name.
+/// The name remains valid until the handle is closed.
+///
+/// # Safety
+///
+/// `handle` must be a pointer returned by [`psf_open`] that has not been closed.
+#[no_mangle]
+pub unsafe extern "C" fn psf_signal_name(handle: *const PsfHandle, index: usize) -> *const c_char {
+    catch(ptr::null(), || {
+        let names = &handle_ref(handle)?.names;
+        let name = names
+            .get(index)
+            .ok_or_else(|| anyhow!("signal index {index} out of range for {}", names.len()))?;
+        Ok(name.as_ptr())
+    })
+}
+
+/// Gets the number of points in the signal named `name`, or -1 if there is no such signal.
+///
+/// # Safety
+///
+/// `handle` must be a pointer returned by [`psf_open`] that has not been closed,
+/// and `name` must be a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn psf_signal_len(handle: *const PsfHandle, name: *const c_char) -> isize {
+    catch(-1, || {
+        Ok(handle_ref(handle)?.values(string(name)?)?.len() as isize)
+    })
+}
+
+/// Returns 1 if the signal named `name` is complex, 0 if it is real,
+/// or -1 if there is no such signal.
+///
+/// # Safety
+///
+/// `handle` must be a pointer returned by [`psf_open`] that has not been closed,
+/// and `name` must be a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn psf_signal_is_complex(
+    handle: *const PsfHandle,
+    name: *const c_char,
+) -> c_int {
+    catch(-1, || match handle_ref(handle)?.values(string(name)?)? {
+        Values::Real(_) => Ok(0),
+        Values::Complex(_) => Ok(1),
+    })
+}
+
+/// Copies up to `len` points of the real signal named `name` into `buf`.
+///
+/// Returns the number of points in the signal, which may exceed `len`,
+/// or -1 if there is no such signal or it is complex.
+///
+/// # Safety
+///
+/// `handle` must be a pointer returned by [`psf_open`] that has not been closed,
+/// `name` must be a valid NUL-terminated string,
+/// and `buf` must be valid for writing `len` values.
+#[no_mangle]
+pub unsafe extern "C" fn psf_copy_signal(
+    handle: *const PsfHandle,
+    name: *const c_char,
+    buf: *mut f64,
+    len: usize,
+) -> isize {
+    catch(-1, || {
+        let name = string(name)?;
+        let Values::Real(values) = handle_ref(handle)?.values(name)? else {
+            bail!("signal `{name}` is complex");
+        };
+        if len > 0 {
+            if buf.is_null() {
+                bail!("null buffer");
+            }
+            let n = values.len().min(len);
+            ptr::copy_nonoverlapping(values.as_ptr(), buf, n);
+        }
+        Ok(values.len() as isize)
+    })
+}
+
+/// Copies up to `len` points of the signal named `name` into `re` and `im`.
+///
+/// The imaginary parts of a real signal are zero.
+/// Returns the number of points in the signal, which may exceed `len`,
+/// or -1 if there is no such signal.
+///
+/// # Safety
+///
+/// `handle` must be a pointer returned by [`psf_open`] that has not been closed,
+/// `name` must be a valid NUL-terminated string,
+/// and `re` and `im` must each be valid for writing `len` values.
+#[no_mangle]
+pub unsafe extern "C" fn psf_copy_complex_signal(
+    handle: *const PsfHandle,
+    name: *const c_char,
+    re: *mut f64,
+    im: *mut f64,
+    len: usize,
+) -> isize {
+    catch(-1, || {
+        let values = handle_ref(handle)?.values(string(name)?)?;
+        if len > 0 {
+            if re.is_null() || im.is_null() {
+                bail!("null buffer");
+            }
+            let n = values.len().min(len);
+            let re = std::slice::from_raw_parts_mut(re, n);
+            let im = std::slice::from_raw_parts_mut(im, n);
+            match values {
+                Values::Real(values) => {
+                    re.copy_from_slice(&values[..n]);
+                    im.fill(0.);
+                }
+                Values::Complex(values) => {
+                    for (i, x) in values[..n].iter().enumerate() {
+                        re[i] = x.re;
+                        im[i] = x.im;
+                    }
+                }
+            }
+        }
+        Ok(values.len() as isize)
+    })
+}
+
+/// Gets the header value named `name`, such as `"simulator"`, formatted as a string.
+///
+/// Returns `NULL` if there is no such header value.
+/// The value remains valid until the handle is closed.
+///
+/// # Safety
+///
+/// `handle` must be a pointer returned by [`psf_open`] that has not been closed,
+/// and `name` must be a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn psf_header_value(
+    handle: *const PsfHandle,
+    name: *const c_char,
+) -> *const c_char {
+    catch(ptr::null(), || {
+        let name = string(name)?;
+        let value = handle_ref(handle)?
+            .header
+            .get(name)
+            .ok_or_else(|| anyhow!("no header value named `{name}`"))?;
+        Ok(value.as_ptr())
+    })
+}
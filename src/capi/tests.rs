@@ -0,0 +1,121 @@
+use std::ffi::{CStr, CString};
+use std::path::Path;
+use std::ptr;
+
+use super::*;
+
+fn example(name: &str) -> CString {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("examples")
+        .join(name);
+    CString::new(path.to_str().unwrap()).unwrap()
+}
+
+unsafe fn str<'a>(s: *const c_char) -> &'a str {
+    CStr::from_ptr(s).to_str().unwrap()
+}
+
+#[test]
+fn header_is_up_to_date() {
+    assert_eq!(
+        HEADER,
+        include_str!("../../include/psfparser.h"),
+        "include/psfparser.h is out of date; copy it from the build output directory"
+    );
+}
+
+#[test]
+fn reads_signals() {
+    unsafe {
+        let psf = psf_open(example("vdiv_sin_bin.tran.tran").as_ptr());
+        assert!(!psf.is_null());
+        assert_eq!(str(psf_header_value(psf, c"simulator".as_ptr())), "spectre");
+        assert!(psf_header_value(psf, c"missing".as_ptr()).is_null());
+
+        let n = psf_signal_count(psf);
+        assert!(n > 1);
+        assert_eq!(str(psf_signal_name(psf, 0)), "time");
+        assert!(psf_signal_name(psf, n).is_null());
+        assert_eq!(psf_signal_len(psf, c"time".as_ptr()), 16001);
+        assert_eq!(psf_signal_is_complex(psf, c"time".as_ptr()), 0);
+        assert_eq!(psf_signal_len(psf, c"missing".as_ptr()), -1);
+        assert_eq!(str(psf_last_error()), "no signal named `missing`");
+
+        let mut buf = [0.; 4];
+        assert_eq!(
+            psf_copy_signal(psf, c"time".as_ptr(), buf.as_mut_ptr(), buf.len()),
+            16001
+        );
+        assert_eq!(buf[0], 0.);
+        assert!(buf[1] > buf[0]);
+        assert_eq!(
+            psf_copy_signal(psf, c"time".as_ptr(), ptr::null_mut(), 0),
+            16001
+        );
+        psf_close(psf);
+    }
+}
+
+#[test]
+fn reads_complex_signals() {
+    unsafe {
+        let psf = psf_open(example("frequencySweep.ac").as_ptr());
+        assert!(!psf.is_null());
+        assert_eq!(str(psf_signal_name(psf, 0)), "freq");
+        assert_eq!(psf_signal_len(psf, c"freq".as_ptr()), 13);
+
+        let name = psf_signal_name(psf, 1);
+        assert_eq!(psf_signal_is_complex(psf, name), 1);
+        let mut buf = [0.; 13];
+        assert_eq!(psf_copy_signal(psf, name, buf.as_mut_ptr(), buf.len()), -1);
+
+        let (mut re, mut im) = ([0.; 13], [0.; 13]);
+        assert_eq!(
+            psf_copy_complex_signal(psf, name, re.as_mut_ptr(), im.as_mut_ptr(), 13),
+            13
+        );
+        let data = (*psf).file.analysis.ac().unwrap();
+        let values = data.signal(str(name)).unwrap();
+        assert_eq!(re[12], values[12].re);
+        assert_eq!(im[12], values[12].im);
+        psf_close(psf);
+    }
+}
+
+#[test]
+fn reports_errors() {
+    unsafe {
+        assert!(psf_open(example("missing.tran").as_ptr()).is_null());
+        assert!(str(psf_last_error()).contains("missing.tran"));
+        assert!(psf_open(ptr::null()).is_null());
+        assert_eq!(psf_signal_count(ptr::null()), 0);
+        assert_eq!(str(psf_last_error()), "null PSF handle");
+        psf_close(ptr::null_mut());
+    }
+}
+
+#[test]
+fn rejects_malformed_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let bin = std::fs::read(
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/vdiv_sin_bin.tran.tran"),
+    )
+    .unwrap();
+    let files: [(&str, &[u8], &str); 2] = [
+        ("truncated.tran", &bin[..2000], "panicked"),
+        (
+            "garbled.tran",
+            b"HEADER\n\"PSFversion\" 1.00\nnonsense\n",
+            "garbled.tran",
+        ),
+    ];
+    for (name, contents, error) in files {
+        let path = dir.path().join(name);
+        std::fs::write(&path, contents).unwrap();
+        let path = CString::new(path.to_str().unwrap()).unwrap();
+        unsafe {
+            assert!(psf_open(path.as_ptr()).is_null());
+            assert!(str(psf_last_error()).contains(error));
+        }
+    }
+}
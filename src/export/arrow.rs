@@ -0,0 +1,163 @@
+//! Apache Arrow and Parquet export.
+//!
+//! Each analysis is converted to a [`RecordBatch`] with one row per sweep point
+//! and the sweep variable as the first column. Complex signals are split into
+//! `re(name)` and `im(name)` columns. The units of each column, where known,
+//! are stored in the `units` field metadata, and PSF header values can be
+//! stored in the schema metadata using `Header::to_strings`.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail};
+use arrow::array::{ArrayRef, Float64Array};
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::record_batch::RecordBatch;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+
+use crate::analysis::ac::AcData;
+use crate::analysis::dc::DcData;
+use crate::analysis::transient::TransientData;
+use crate::Result;
+
+/// Collects the fields and columns of a record batch.
+#[derive(Default)]
+struct Columns {
+    fields: Vec<Field>,
+    arrays: Vec<ArrayRef>,
+}
+
+impl Columns {
+    fn push(&mut self, name: String, units: Option<&str>, values: Vec<f64>) {
+        let mut field = Field::new(name, DataType::Float64, false);
+        if let Some(units) = units.filter(|units| !units.is_empty()) {
+            field.set_metadata(HashMap::from([("units".to_string(), units.to_string())]));
+        }
+        self.fields.push(field);
+        self.arrays.push(Arc::new(Float64Array::from(values)));
+    }
+
+    fn finish(self, metadata: HashMap<String, String>) -> Result<RecordBatch> {
+        let schema = Schema::new_with_metadata(self.fields, metadata);
+        Ok(RecordBatch::try_new(Arc::new(schema), self.arrays)?)
+    }
+}
+
+/// Gets the names of all signals other than the sweep variable, sorted by name.
+fn names<'a, T>(signals: &'a HashMap<String, T>, sweep: &str) -> Vec<&'a String> {
+    let mut names = signals
+        .keys()
+        .filter(|name| *name != sweep)
+        .collect::<Vec<_>>();
+    names.sort_unstable();
+    names
+}
+
+/// Converts the signals of a transient analysis to a record batch,
+/// with time as the first column.
+pub fn transient_batch(
+    data: &TransientData,
+    metadata: HashMap<String, String>,
+) -> Result<RecordBatch> {
+    let mut columns = Columns::default();
+    for name in std::iter::once(&data.time).chain(names(&data.signals, &data.time)) {
+        let values = data
+            .signal(name)
+            .ok_or_else(|| anyhow!("missing time signal `{name}`"))?;
+        columns.push(
+            name.clone(),
+            data.units.get(name).map(String::as_str),
+            values.clone(),
+        );
+    }
+    columns.finish(metadata)
+}
+
+/// Converts the signals of an AC analysis to a record batch,
+/// with frequency as the first column.
+pub fn ac_batch(data: &AcData, metadata: HashMap<String, String>) -> Result<RecordBatch> {
+    let mut columns = Columns::default();
+    columns.push(
+        "freq".to_string(),
+        Some(data.units.get("freq").map_or("Hz", String::as_str)),
+        data.freq.clone(),
+    );
+    for name in names(&data.signals, "freq") {
+        let values = &data.signals[name];
+        let units = data.units.get(name).map(String::as_str);
+        columns.push(
+            format!("re({name})"),
+            units,
+            values.iter().map(|x| x.re).collect(),
+        );
+        columns.push(
+            format!("im({name})"),
+            units,
+            values.iter().map(|x| x.im).collect(),
+        );
+    }
+    columns.finish(metadata)
+}
+
+/// Converts the signals of a DC analysis to a record batch.
+///
+/// A sweep has the swept parameter as the first column.
+/// An operating point is converted to a single row.
+pub fn dc_batch(data: &DcData, metadata: HashMap<String, String>) -> Result<RecordBatch> {
+    let mut columns = Columns::default();
+    match data {
+        DcData::Sweep(data) => {
+            let (param, values) = &data.param;
+            columns.push(
+                param.clone(),
+                data.units.get(param).map(String::as_str),
+                values.clone(),
+            );
+            for name in names(&data.signals, param) {
+                columns.push(
+                    name.clone(),
+                    data.units.get(name).map(String::as_str),
+                    data.signals[name].clone(),
+                );
+            }
+        }
+        DcData::Op(data) => {
+            for name in names(&data.signals, "") {
+                columns.push(
+                    name.clone(),
+                    data.units.get(name).map(String::as_str),
+                    vec![data.signals[name]],
+                );
+            }
+        }
+    }
+    columns.finish(metadata)
+}
+
+/// Writes record batches to a Parquet file.
+///
+/// All batches must have the same schema. If `properties` is `None`,
+/// columns are compressed with Snappy.
+pub fn write_parquet(
+    w: impl Write + Send,
+    batches: &[RecordBatch],
+    properties: Option<WriterProperties>,
+) -> Result<()> {
+    let Some(first) = batches.first() else {
+        bail!("no record batches to write");
+    };
+    let properties = properties.unwrap_or_else(|| {
+        WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build()
+    });
+    let mut writer = ArrowWriter::try_new(w, first.schema(), Some(properties))?;
+    for batch in batches {
+        writer.write(batch)?;
+    }
+    writer.close()?;
+    Ok(())
+}
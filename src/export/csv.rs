@@ -0,0 +1,262 @@
+//! CSV and TSV export.
+//!
+//! Each function writes one row per sweep point, preceded by a header row
+//! containing each column's signal name and, where known, its units.
+//! Rows are written as they are formatted, so the output is never buffered in full.
+
+use std::collections::HashMap;
+use std::io::{BufWriter, Write};
+
+use anyhow::{anyhow, bail};
+use num::complex::Complex64;
+
+use crate::analysis::ac::AcData;
+use crate::analysis::dc::DcData;
+use crate::analysis::transient::TransientData;
+use crate::Result;
+
+/// Options controlling CSV output.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvOptions {
+    /// The field separator, such as `,` for CSV or `\t` for TSV.
+    pub delimiter: char,
+    /// The signals to write, in order.
+    ///
+    /// If `None`, all signals are written, sorted by name.
+    /// The sweep variable is always written as the first column.
+    pub signals: Option<Vec<String>>,
+    /// The columns written for each complex signal.
+    pub complex: Vec<ComplexPart>,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: ',',
+            signals: None,
+            complex: vec![ComplexPart::Real, ComplexPart::Imag],
+        }
+    }
+}
+
+impl CsvOptions {
+    /// Options for tab separated output.
+    pub fn tsv() -> Self {
+        Self {
+            delimiter: '\t',
+            ..Self::default()
+        }
+    }
+}
+
+/// A real-valued component of a complex signal.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum ComplexPart {
+    Real,
+    Imag,
+    Mag,
+    /// The phase, in degrees.
+    Phase,
+    /// The magnitude, in dB.
+    Db20,
+}
+
+impl ComplexPart {
+    fn eval(self, x: Complex64) -> f64 {
+        match self {
+            Self::Real => x.re,
+            Self::Imag => x.im,
+            Self::Mag => x.norm(),
+            Self::Phase => x.arg().to_degrees(),
+            Self::Db20 => 20. * x.norm().log10(),
+        }
+    }
+
+    fn header(self, name: &str, units: Option<&str>) -> String {
+        let (prefix, units) = match self {
+            Self::Real => ("re", units),
+            Self::Imag => ("im", units),
+            Self::Mag => ("mag", units),
+            Self::Phase => ("phase", Some("deg")),
+            Self::Db20 => ("db20", Some("dB")),
+        };
+        header(&format!("{prefix}({name})"), units)
+    }
+}
+
+enum Column<'a> {
+    Real(&'a [f64]),
+    Complex(&'a [Complex64], ComplexPart),
+}
+
+impl Column<'_> {
+    fn len(&self) -> usize {
+        match self {
+            Self::Real(values) => values.len(),
+            Self::Complex(values, _) => values.len(),
+        }
+    }
+
+    fn value(&self, i: usize) -> f64 {
+        match self {
+            Self::Real(values) => values[i],
+            Self::Complex(values, part) => part.eval(values[i]),
+        }
+    }
+}
+
+/// Writes the signals of a transient analysis, with time as the first column.
+pub fn write_transient(w: impl Write, data: &TransientData, options: &CsvOptions) -> Result<()> {
+    let time = data
+        .signal(&data.time)
+        .ok_or_else(|| anyhow!("missing time signal `{}`", data.time))?;
+    let mut headers = vec![header(&data.time, data.units.get(&data.time))];
+    let mut columns = vec![Column::Real(time)];
+    for name in select(&data.signals, &data.time, options)? {
+        headers.push(header(name, data.units.get(name)));
+        columns.push(Column::Real(&data.signals[name]));
+    }
+    write_columns(w, &headers, &columns, options.delimiter)
+}
+
+/// Writes the signals of an AC analysis, with frequency as the first column.
+///
+/// Each signal is written as the columns given by [`CsvOptions::complex`].
+pub fn write_ac(w: impl Write, data: &AcData, options: &CsvOptions) -> Result<()> {
+    let mut headers = vec![header(
+        "freq",
+        Some(data.units.get("freq").map_or("Hz", String::as_str)),
+    )];
+    let mut columns = vec![Column::Real(&data.freq)];
+    for name in select(&data.signals, "freq", options)? {
+        let units = data.units.get(name).map(String::as_str);
+        for &part in options.complex.iter() {
+            headers.push(part.header(name, units));
+            columns.push(Column::Complex(&data.signals[name], part));
+        }
+    }
+    write_columns(w, &headers, &columns, options.delimiter)
+}
+
+/// Writes the signals of a DC analysis.
+///
+/// A sweep is written with the swept parameter as the first column.
+/// An operating point is written as a single row.
+pub fn write_dc(w: impl Write, data: &DcData, options: &CsvOptions) -> Result<()> {
+    match data {
+        DcData::Sweep(data) => {
+            let (param, values) = &data.param;
+            let mut headers = vec![header(param, data.units.get(param))];
+            let mut columns = vec![Column::Real(values)];
+            for name in select(&data.signals, param, options)? {
+                headers.push(header(name, data.units.get(name)));
+                columns.push(Column::Real(&data.signals[name]));
+            }
+            write_columns(w, &headers, &columns, options.delimiter)
+        }
+        DcData::Op(data) => {
+            let mut headers = Vec::new();
+            let mut columns = Vec::new();
+            for name in select(&data.signals, "", options)? {
+                headers.push(header(name, data.units.get(name)));
+                columns.push(Column::Real(std::slice::from_ref(&data.signals[name])));
+            }
+            write_columns(w, &headers, &columns, options.delimiter)
+        }
+    }
+}
+
+/// Chooses the signals to write, excluding the sweep variable.
+fn select<'a, T>(
+    signals: &'a HashMap<String, T>,
+    sweep: &str,
+    options: &'a CsvOptions,
+) -> Result<Vec<&'a str>> {
+    match options.signals {
+        Some(ref names) => {
+            for name in names.iter() {
+                if !signals.contains_key(name) {
+                    bail!("no signal named `{name}`");
+                }
+            }
+            Ok(names
+                .iter()
+                .filter(|name| *name != sweep)
+                .map(String::as_str)
+                .collect())
+        }
+        None => {
+            let mut names = signals
+                .keys()
+                .filter(|name| *name != sweep)
+                .map(String::as_str)
+                .collect::<Vec<_>>();
+            names.sort_unstable();
+            Ok(names)
+        }
+    }
+}
+
+fn header(name: &str, units: Option<impl AsRef<str>>) -> String {
+    match units {
+        Some(units) if !units.as_ref().is_empty() => format!("{name} ({})", units.as_ref()),
+        _ => name.to_string(),
+    }
+}
+
+fn write_columns(
+    w: impl Write,
+    headers: &[String],
+    columns: &[Column],
+    delimiter: char,
+) -> Result<()> {
+    let rows = columns.first().map_or(0, Column::len);
+    if columns.iter().any(|col| col.len() != rows) {
+        bail!("signals do not all have the same number of points");
+    }
+
+    let mut w = BufWriter::new(w);
+    let mut sep = [0; 4];
+    let sep = delimiter.encode_utf8(&mut sep).as_bytes();
+    for (j, h) in headers.iter().enumerate() {
+        if j > 0 {
+            w.write_all(sep)?;
+        }
+        write_field(&mut w, h, delimiter)?;
+    }
+    writeln!(w)?;
+
+    for i in 0..rows {
+        for (j, col) in columns.iter().enumerate() {
+            if j > 0 {
+                w.write_all(sep)?;
+            }
+            write_float(&mut w, col.value(i))?;
+        }
+        writeln!(w)?;
+    }
+    w.flush()?;
+    Ok(())
+}
+
+/// Writes a text field, quoting it if it contains special characters.
+fn write_field(w: &mut impl Write, field: &str, delimiter: char) -> Result<()> {
+    if field.contains([delimiter, '"', '\n', '\r']) {
+        write!(w, "\"{}\"", field.replace('"', "\"\""))?;
+    } else {
+        w.write_all(field.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Writes a number using the shortest representation that round-trips,
+/// switching to scientific notation for very large or small magnitudes.
+fn write_float(w: &mut impl Write, x: f64) -> Result<()> {
+    let mag = x.abs();
+    if x == 0. || !x.is_finite() || (1e-3..1e15).contains(&mag) {
+        write!(w, "{x}")?;
+    } else {
+        write!(w, "{x:e}")?;
+    }
+    Ok(())
+}
@@ -0,0 +1,11 @@
+//! Conversion of analysis results to other file formats.
+
+#[cfg(feature = "arrow")]
+pub mod arrow;
+pub mod csv;
+#[cfg(feature = "npy")]
+pub mod npy;
+pub mod vcd;
+
+#[cfg(test)]
+mod tests;
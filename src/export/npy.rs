@@ -0,0 +1,206 @@
+//! NumPy `.npy` and `.npz` export.
+//!
+//! Real signals are written as `float64` arrays and complex signals as `complex128` arrays,
+//! both little-endian. An `.npz` bundle contains one array per signal, named after the
+//! signal, plus the sweep axis; it can be read with `np.load`.
+
+use std::collections::HashMap;
+use std::io::{BufWriter, Seek, Write};
+
+use anyhow::{anyhow, bail};
+use num::complex::Complex64;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::analysis::ac::AcData;
+use crate::analysis::dc::DcData;
+use crate::analysis::transient::TransientData;
+use crate::Result;
+
+/// A type that can be stored in a `.npy` array.
+pub trait Element: Copy {
+    /// The NumPy type descriptor of this type.
+    const DESCR: &'static str;
+
+    /// Size of this type in bytes.
+    const SIZE: usize;
+
+    fn write_le(self, w: &mut impl Write) -> std::io::Result<()>;
+}
+
+impl Element for f64 {
+    const DESCR: &'static str = "<f8";
+    const SIZE: usize = 8;
+
+    fn write_le(self, w: &mut impl Write) -> std::io::Result<()> {
+        w.write_all(&self.to_le_bytes())
+    }
+}
+
+impl Element for Complex64 {
+    const DESCR: &'static str = "<c16";
+    const SIZE: usize = 16;
+
+    fn write_le(self, w: &mut impl Write) -> std::io::Result<()> {
+        w.write_all(&self.re.to_le_bytes())?;
+        w.write_all(&self.im.to_le_bytes())
+    }
+}
+
+/// Writes a one-dimensional `.npy` array.
+pub fn write_npy<T: Element>(w: impl Write, values: &[T]) -> Result<()> {
+    write_array(w, values, &[values.len()])
+}
+
+/// Writes a `.npy` array with the given shape.
+///
+/// Values are in C (row-major) order.
+fn write_array<T: Element>(mut w: impl Write, values: &[T], shape: &[usize]) -> Result<()> {
+    let shape = match shape {
+        [n] => format!("({n},)"),
+        _ => format!(
+            "({})",
+            shape
+                .iter()
+                .map(usize::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+    let mut header = format!(
+        "{{'descr': '{}', 'fortran_order': False, 'shape': {shape}, }}",
+        T::DESCR
+    );
+    // The magic string, version and header length take 10 bytes, and the
+    // header is padded with spaces so that the data is 64 byte aligned.
+    let len = 10 + header.len() + 1;
+    header.extend(std::iter::repeat_n(' ', len.next_multiple_of(64) - len));
+    header.push('\n');
+
+    w.write_all(b"\x93NUMPY\x01\x00")?;
+    w.write_all(&u16::try_from(header.len())?.to_le_bytes())?;
+    w.write_all(header.as_bytes())?;
+    let mut w = BufWriter::new(w);
+    for &x in values {
+        x.write_le(&mut w)?;
+    }
+    w.flush()?;
+    Ok(())
+}
+
+/// Writes arrays to a `.npz` archive.
+struct Npz<W: Write + Seek> {
+    zip: ZipWriter<W>,
+}
+
+impl<W: Write + Seek> Npz<W> {
+    fn new(w: W) -> Self {
+        Self {
+            zip: ZipWriter::new(w),
+        }
+    }
+
+    fn add<T: Element>(&mut self, name: &str, values: &[T], shape: &[usize]) -> Result<()> {
+        let options = SimpleFileOptions::default()
+            .compression_method(CompressionMethod::Stored)
+            .large_file(values.len() * T::SIZE >= u32::MAX as usize);
+        self.zip.start_file(format!("{name}.npy"), options)?;
+        write_array(&mut self.zip, values, shape)
+    }
+
+    fn finish(self) -> Result<()> {
+        self.zip.finish()?.flush()?;
+        Ok(())
+    }
+}
+
+/// Chooses the signals to write, sorted by name, excluding the sweep variable.
+fn select<'a, T>(
+    signals: &'a HashMap<String, T>,
+    sweep: &str,
+    names: Option<&'a [&'a str]>,
+) -> Result<Vec<&'a str>> {
+    match names {
+        Some(names) => {
+            for name in names {
+                if !signals.contains_key(*name) {
+                    bail!("no signal named `{name}`");
+                }
+            }
+            Ok(names
+                .iter()
+                .copied()
+                .filter(|name| *name != sweep)
+                .collect())
+        }
+        None => {
+            let mut names = signals
+                .keys()
+                .filter(|name| *name != sweep)
+                .map(String::as_str)
+                .collect::<Vec<_>>();
+            names.sort_unstable();
+            Ok(names)
+        }
+    }
+}
+
+/// Writes the signals of a transient analysis as a `.npz` archive,
+/// including the time signal.
+///
+/// If `signals` is `None`, all signals are written.
+pub fn write_transient(
+    w: impl Write + Seek,
+    data: &TransientData,
+    signals: Option<&[&str]>,
+) -> Result<()> {
+    let time = data
+        .signal(&data.time)
+        .ok_or_else(|| anyhow!("missing time signal `{}`", data.time))?;
+    let mut npz = Npz::new(w);
+    npz.add(&data.time, time, &[time.len()])?;
+    for name in select(&data.signals, &data.time, signals)? {
+        let values = &data.signals[name];
+        npz.add(name, values, &[values.len()])?;
+    }
+    npz.finish()
+}
+
+/// Writes the signals of an AC analysis as a `.npz` archive of complex arrays,
+/// with the frequency axis in the array `freq`.
+///
+/// If `signals` is `None`, all signals are written.
+pub fn write_ac(w: impl Write + Seek, data: &AcData, signals: Option<&[&str]>) -> Result<()> {
+    let mut npz = Npz::new(w);
+    npz.add("freq", &data.freq, &[data.freq.len()])?;
+    for name in select(&data.signals, "freq", signals)? {
+        let values = &data.signals[name];
+        npz.add(name, values, &[values.len()])?;
+    }
+    npz.finish()
+}
+
+/// Writes the signals of a DC analysis as a `.npz` archive.
+///
+/// A sweep includes the swept parameter as an array.
+/// Each signal of an operating point is written as a zero-dimensional array.
+/// If `signals` is `None`, all signals are written.
+pub fn write_dc(w: impl Write + Seek, data: &DcData, signals: Option<&[&str]>) -> Result<()> {
+    let mut npz = Npz::new(w);
+    match data {
+        DcData::Sweep(data) => {
+            let (param, values) = &data.param;
+            npz.add(param, values, &[values.len()])?;
+            for name in select(&data.signals, param, signals)? {
+                let values = &data.signals[name];
+                npz.add(name, values, &[values.len()])?;
+            }
+        }
+        DcData::Op(data) => {
+            for name in select(&data.signals, "", signals)? {
+                npz.add(name, std::slice::from_ref(&data.signals[name]), &[])?;
+            }
+        }
+    }
+    npz.finish()
+}
@@ -0,0 +1,358 @@
+use std::collections::HashMap;
+
+use crate::analysis::ac::AcData;
+use crate::analysis::dc::DcData;
+use crate::analysis::digital::Threshold;
+use crate::analysis::transient::TransientData;
+use crate::ascii::parse as ascii_parse;
+use crate::ascii::tests::{AC_EXAMPLE_PSF, DC_EXAMPLE1_PSF, TRAN_EXAMPLE2_PSF, VDIV_SIN_PSF};
+
+use super::csv::{write_ac, write_dc, write_transient, ComplexPart, CsvOptions};
+#[cfg(feature = "npy")]
+use super::npy;
+use super::vcd::{self, VcdOptions};
+
+fn lines(out: Vec<u8>) -> Vec<String> {
+    String::from_utf8(out)
+        .unwrap()
+        .lines()
+        .map(str::to_string)
+        .collect()
+}
+
+#[test]
+fn transient_csv() {
+    let ast = ascii_parse(VDIV_SIN_PSF).unwrap();
+    let data = TransientData::from_ascii(&ast);
+    let mut out = Vec::new();
+    write_transient(&mut out, &data, &CsvOptions::default()).unwrap();
+
+    let lines = lines(out);
+    let time = data.signal("time").unwrap();
+    assert_eq!(lines.len(), time.len() + 1);
+    assert!(lines[0].starts_with("time (s),"));
+    assert_eq!(lines[0].split(',').count(), data.signals.len());
+    for (line, t) in lines[1..].iter().zip(time.iter()) {
+        let first = line.split(',').next().unwrap();
+        assert_eq!(first.parse::<f64>().unwrap(), *t);
+    }
+}
+
+#[test]
+fn transient_tsv_selection() {
+    let ast = ascii_parse(VDIV_SIN_PSF).unwrap();
+    let data = TransientData::from_ascii(&ast);
+    let name = data
+        .signals
+        .keys()
+        .find(|name| **name != data.time)
+        .unwrap()
+        .clone();
+    let options = CsvOptions {
+        signals: Some(vec![name.clone()]),
+        ..CsvOptions::tsv()
+    };
+    let mut out = Vec::new();
+    write_transient(&mut out, &data, &options).unwrap();
+
+    let lines = lines(out);
+    let header = lines[0].split('\t').collect::<Vec<_>>();
+    assert_eq!(header.len(), 2);
+    assert!(header[1].starts_with(&name));
+    let value = lines[5].split('\t').nth(1).unwrap().parse::<f64>().unwrap();
+    assert_eq!(value, data.signal(&name).unwrap()[4]);
+
+    let options = CsvOptions {
+        signals: Some(vec!["missing".to_string()]),
+        ..CsvOptions::default()
+    };
+    assert!(write_transient(Vec::new(), &data, &options).is_err());
+}
+
+#[test]
+fn ac_csv_columns() {
+    let ast = ascii_parse(AC_EXAMPLE_PSF).unwrap();
+    let data = AcData::from_ascii(&ast);
+    let name = data.signals.keys().min().unwrap().clone();
+    let options = CsvOptions {
+        signals: Some(vec![name.clone()]),
+        complex: vec![ComplexPart::Mag, ComplexPart::Phase, ComplexPart::Db20],
+        ..CsvOptions::default()
+    };
+    let mut out = Vec::new();
+    write_ac(&mut out, &data, &options).unwrap();
+
+    let lines = lines(out);
+    assert_eq!(lines.len(), data.freq.len() + 1);
+    let header = lines[0].split(',').collect::<Vec<_>>();
+    assert_eq!(header.len(), 4);
+    assert!(header[0].starts_with("freq"));
+    assert!(header[2].ends_with("(deg)"));
+    assert!(header[3].ends_with("(dB)"));
+
+    let x = data.signal(&name).unwrap()[0];
+    let row = lines[1]
+        .split(',')
+        .map(|v| v.parse::<f64>().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(row[1], x.norm());
+    assert_eq!(row[2], x.arg().to_degrees());
+    assert_eq!(row[3], 20. * x.norm().log10());
+}
+
+#[test]
+fn dc_sweep_csv() {
+    let ast = ascii_parse(DC_EXAMPLE1_PSF).unwrap();
+    let data = DcData::from_ast(&ast);
+    let mut out = Vec::new();
+    write_dc(&mut out, &data, &CsvOptions::default()).unwrap();
+
+    let lines = lines(out);
+    assert_eq!(lines[0], "vddval,Vvdd:p (A),vdd (V),vout (V)");
+    let DcData::Sweep(sweep) = data else {
+        panic!("expected a dc sweep");
+    };
+    assert_eq!(lines.len(), sweep.param.1.len() + 1);
+}
+
+#[test]
+fn vcd_bus() {
+    let signals = [
+        ("time", [0., 1e-9, 2e-9, 3e-9]),
+        ("v(clk)", [0., 1., 0., 1.]),
+        ("v(d[0])", [0., 0., 1., 1.]),
+        ("v(d[1])", [1., 1., 1., 0.]),
+        ("i(vdd)", [0., 1e-3, 0., 1e-3]),
+    ];
+    let data = TransientData {
+        signals: signals
+            .iter()
+            .map(|(name, values)| (name.to_string(), values.to_vec()))
+            .collect(),
+        time: "time".to_string(),
+        units: HashMap::new(),
+    };
+    let mut options = VcdOptions::new(Threshold::cmos(1.).unwrap().with_hysteresis());
+    options.signals = Some(vec![
+        "v(clk)".to_string(),
+        "v(d[0])".to_string(),
+        "v(d[1])".to_string(),
+    ]);
+    let mut out = Vec::new();
+    vcd::write_transient(&mut out, &data, &options).unwrap();
+    let expected = [
+        "$version psfparser 0.1.2 $end",
+        "$timescale 1ps $end",
+        "$scope module top $end",
+        "$var wire 1 ! clk $end",
+        "$var wire 2 \" d [1:0] $end",
+        "$upscope $end",
+        "$enddefinitions $end",
+        "#0",
+        "$dumpvars",
+        "0!",
+        "b10 \"",
+        "$end",
+        "#700",
+        "1!",
+        "#1700",
+        "0!",
+        "b11 \"",
+        "#2700",
+        "1!",
+        "b01 \"",
+    ];
+    assert_eq!(lines(out), expected);
+
+    options.threshold = Threshold::cmos(1.).unwrap();
+    options.timescale = 1e-9;
+    let mut out = Vec::new();
+    vcd::write_transient(&mut out, &data, &options).unwrap();
+    let lines = lines(out);
+    assert!(lines.contains(&"$timescale 1ns $end".to_string()));
+    assert!(lines.contains(&"x!".to_string()));
+
+    options.timescale = 2e-12;
+    assert!(vcd::write_transient(Vec::new(), &data, &options).is_err());
+
+    options.timescale = 1e-12;
+    let mut gapped = data.clone();
+    let d1 = gapped.signals.remove("v(d[1])").unwrap();
+    gapped.signals.insert("v(d[2])".to_string(), d1);
+    options.signals = Some(vec!["v(d[0])".to_string(), "v(d[2])".to_string()]);
+    let err = vcd::write_transient(Vec::new(), &gapped, &options).unwrap_err();
+    assert!(err.to_string().contains("missing bit 1"));
+}
+
+#[test]
+fn vcd_sram_buses() {
+    let ast = ascii_parse(TRAN_EXAMPLE2_PSF).unwrap();
+    let data = TransientData::from_ascii(&ast);
+    let vdd = data
+        .signals
+        .iter()
+        .filter(|(name, _)| **name != data.time)
+        .flat_map(|(_, values)| values.iter().copied())
+        .fold(0., f64::max);
+    let mut out = Vec::new();
+    vcd::write_transient(
+        &mut out,
+        &data,
+        &VcdOptions::new(Threshold::cmos(vdd).unwrap()),
+    )
+    .unwrap();
+
+    let lines = lines(out);
+    assert_eq!(
+        lines[3..9],
+        [
+            "$var wire 6 ! addr [5:0] $end",
+            "$var wire 1 \" clk $end",
+            "$var wire 16 # din [15:0] $end",
+            "$var wire 16 $ dout [15:0] $end",
+            "$var wire 1 % we $end",
+            "$upscope $end",
+        ]
+    );
+    let edges = lines.iter().filter(|line| *line == "1\"").count();
+    assert!(edges > 1);
+}
+
+#[cfg(feature = "npy")]
+/// Splits a `.npy` file into its header dictionary and data.
+fn read_npy(bytes: &[u8]) -> (&str, &[u8]) {
+    assert_eq!(&bytes[..8], b"\x93NUMPY\x01\x00");
+    let len = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+    assert_eq!((10 + len) % 64, 0);
+    let header = std::str::from_utf8(&bytes[10..10 + len]).unwrap();
+    assert!(header.ends_with('\n'));
+    (header.trim_end(), &bytes[10 + len..])
+}
+
+#[cfg(feature = "npy")]
+fn f64s(data: &[u8]) -> Vec<f64> {
+    data.chunks_exact(8)
+        .map(|b| f64::from_le_bytes(b.try_into().unwrap()))
+        .collect()
+}
+
+#[cfg(feature = "npy")]
+#[test]
+fn npy_arrays() {
+    let mut out = Vec::new();
+    npy::write_npy(&mut out, &[1.5, -2., 1e-12]).unwrap();
+    let (header, data) = read_npy(&out);
+    assert_eq!(
+        header,
+        "{'descr': '<f8', 'fortran_order': False, 'shape': (3,), }"
+    );
+    assert_eq!(f64s(data), [1.5, -2., 1e-12]);
+
+    let mut out = Vec::new();
+    npy::write_npy(&mut out, &[num::complex::Complex64::new(1., -1.)]).unwrap();
+    let (header, data) = read_npy(&out);
+    assert!(header.contains("'descr': '<c16'"));
+    assert_eq!(f64s(data), [1., -1.]);
+}
+
+#[cfg(feature = "npy")]
+#[test]
+fn npz_bundles() {
+    use std::io::{Cursor, Read};
+
+    let read = |out: Vec<u8>| {
+        let mut archive = zip::ZipArchive::new(Cursor::new(out)).unwrap();
+        let mut arrays = HashMap::new();
+        for i in 0..archive.len() {
+            let mut file = archive.by_index(i).unwrap();
+            let mut bytes = Vec::new();
+            file.read_to_end(&mut bytes).unwrap();
+            arrays.insert(file.name().to_string(), bytes);
+        }
+        arrays
+    };
+
+    let data = TransientData::from_ascii(&ascii_parse(VDIV_SIN_PSF).unwrap());
+    let mut out = Cursor::new(Vec::new());
+    npy::write_transient(&mut out, &data, None).unwrap();
+    let arrays = read(out.into_inner());
+    assert_eq!(arrays.len(), data.signals.len());
+    for (name, values) in data.signals.iter() {
+        let (_, bytes) = read_npy(&arrays[&format!("{name}.npy")]);
+        assert_eq!(&f64s(bytes), values);
+    }
+
+    let data = AcData::from_ascii(&ascii_parse(AC_EXAMPLE_PSF).unwrap());
+    let name = data.signals.keys().min().unwrap().as_str();
+    let mut out = Cursor::new(Vec::new());
+    npy::write_ac(&mut out, &data, Some(&[name])).unwrap();
+    let arrays = read(out.into_inner());
+    assert_eq!(arrays.len(), 2);
+    let (header, bytes) = read_npy(&arrays["freq.npy"]);
+    assert!(header.contains("'descr': '<f8'"));
+    assert_eq!(f64s(bytes), data.freq);
+    let (header, bytes) = read_npy(&arrays[&format!("{name}.npy")]);
+    assert!(header.contains("'descr': '<c16'"));
+    let x = data.signal(name).unwrap()[1];
+    assert_eq!(f64s(bytes)[2..4], [x.re, x.im]);
+    assert!(npy::write_ac(Cursor::new(Vec::new()), &data, Some(&["missing"])).is_err());
+
+    let data = DcData::from_ast(&ascii_parse(DC_EXAMPLE1_PSF).unwrap());
+    let mut out = Cursor::new(Vec::new());
+    npy::write_dc(&mut out, &data, None).unwrap();
+    let arrays = read(out.into_inner());
+    assert!(arrays.contains_key("vddval.npy"));
+    assert!(arrays.contains_key("vout.npy"));
+}
+
+#[cfg(feature = "arrow")]
+#[test]
+fn arrow_batches_to_parquet() {
+    use arrow::array::{Array, Float64Array};
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    use super::arrow::{ac_batch, dc_batch, transient_batch, write_parquet};
+
+    let ast = ascii_parse(VDIV_SIN_PSF).unwrap();
+    let data = TransientData::from_ascii(&ast);
+    let batch = transient_batch(&data, ast.header.to_strings()).unwrap();
+    assert_eq!(batch.num_columns(), data.signals.len());
+    assert_eq!(batch.num_rows(), data.signal("time").unwrap().len());
+    let schema = batch.schema();
+    assert_eq!(schema.field(0).name(), "time");
+    assert_eq!(schema.field(0).metadata()["units"], "s");
+    assert_eq!(schema.metadata()["simulator"], "spectre");
+
+    let mut file = tempfile::tempfile().unwrap();
+    write_parquet(&mut file, std::slice::from_ref(&batch), None).unwrap();
+    let builder = ParquetRecordBatchReaderBuilder::try_new(file).unwrap();
+    assert_eq!(builder.schema(), &schema);
+    let reader = builder.with_batch_size(batch.num_rows()).build().unwrap();
+    let batches = reader.collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(batches.len(), 1);
+    assert_eq!(batches[0].columns(), batch.columns());
+    assert!(write_parquet(Vec::new(), &[], None).is_err());
+
+    let ast = ascii_parse(AC_EXAMPLE_PSF).unwrap();
+    let data = AcData::from_ascii(&ast);
+    let batch = ac_batch(&data, HashMap::new()).unwrap();
+    assert_eq!(batch.num_columns(), 2 * data.signals.len() + 1);
+    let name = data.signals.keys().min().unwrap();
+    let im = batch
+        .column_by_name(&format!("im({name})"))
+        .unwrap()
+        .as_any()
+        .downcast_ref::<Float64Array>()
+        .unwrap();
+    assert_eq!(im.len(), data.freq.len());
+    assert_eq!(im.value(1), data.signal(name).unwrap()[1].im);
+
+    let data = DcData::from_ast(&ascii_parse(DC_EXAMPLE1_PSF).unwrap());
+    let batch = dc_batch(&data, HashMap::new()).unwrap();
+    let schema = batch.schema();
+    assert_eq!(schema.field(0).name(), "vddval");
+    assert_eq!(
+        schema.field_with_name("vout").unwrap().metadata()["units"],
+        "V"
+    );
+}
@@ -0,0 +1,246 @@
+//! VCD export of analog waveforms converted to logic levels.
+//!
+//! Each selected signal is digitized with a [`Threshold`]. Signals named like
+//! `v(dout[3])` or `dout[3]` are grouped into a single bus variable (`dout`),
+//! with the highest bit index as the most significant bit.
+
+use std::collections::HashMap;
+use std::io::{BufWriter, Write};
+
+use anyhow::{anyhow, bail};
+use float_eq::float_eq;
+
+use crate::analysis::digital::{bus_bit, level_at, strip_probe, Logic, Threshold};
+use crate::analysis::transient::TransientData;
+use crate::Result;
+
+/// Options controlling VCD output.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VcdOptions {
+    /// The thresholds used to convert each signal to logic levels.
+    pub threshold: Threshold,
+    /// The time unit of the output, in seconds.
+    ///
+    /// Must be 1, 10 or 100 times one of s, ms, us, ns, ps or fs.
+    /// Threshold crossings are rounded to the nearest multiple of this unit.
+    pub timescale: f64,
+    /// The signals to convert, in order.
+    ///
+    /// If `None`, all signals except time are converted, sorted by name.
+    pub signals: Option<Vec<String>>,
+    /// The name of the module scope containing the variables.
+    pub scope: String,
+}
+
+impl VcdOptions {
+    /// Creates options with a timescale of 1 ps that convert all signals.
+    pub fn new(threshold: Threshold) -> Self {
+        Self {
+            threshold,
+            timescale: 1e-12,
+            signals: None,
+            scope: "top".to_string(),
+        }
+    }
+}
+
+/// A VCD variable: either a single signal or a bus.
+struct Var {
+    name: String,
+    bus: bool,
+    /// The index and level changes of each bit, most significant first.
+    bits: Vec<(usize, Vec<(f64, Logic)>)>,
+}
+
+impl Var {
+    /// Gets the value of this variable at each time at which any of its bits changes.
+    fn changes(&self) -> Vec<(f64, String)> {
+        let mut times = self
+            .bits
+            .iter()
+            .flat_map(|(_, events)| events.iter().map(|(t, _)| *t))
+            .collect::<Vec<_>>();
+        times.sort_by(f64::total_cmp);
+        times.dedup();
+        times
+            .into_iter()
+            .map(|t| {
+                let value = self
+                    .bits
+                    .iter()
+                    .map(|(_, events)| level_at(events, t).unwrap_or(Logic::X).to_char())
+                    .collect();
+                (t, value)
+            })
+            .collect()
+    }
+
+    fn declaration(&self, id: &str) -> String {
+        let name = self.name.replace(char::is_whitespace, "_");
+        match (self.bus, self.bits.first(), self.bits.last()) {
+            (true, Some((msb, _)), Some((lsb, _))) => format!(
+                "$var wire {} {id} {name} [{msb}:{lsb}] $end",
+                self.bits.len()
+            ),
+            _ => format!("$var wire 1 {id} {name} $end"),
+        }
+    }
+
+    fn value(&self, value: &str, id: &str) -> String {
+        if self.bus {
+            format!("b{value} {id}")
+        } else {
+            format!("{value}{id}")
+        }
+    }
+}
+
+/// Writes the selected signals of a transient analysis as logic levels in VCD format.
+pub fn write_transient(w: impl Write, data: &TransientData, options: &VcdOptions) -> Result<()> {
+    let time = data
+        .signal(&data.time)
+        .ok_or_else(|| anyhow!("missing time signal `{}`", data.time))?;
+    let timescale = timescale(options.timescale)?;
+
+    let names = match options.signals {
+        Some(ref names) => names.iter().map(String::as_str).collect(),
+        None => {
+            let mut names = data
+                .signals
+                .keys()
+                .filter(|name| **name != data.time)
+                .map(String::as_str)
+                .collect::<Vec<_>>();
+            names.sort_unstable();
+            names
+        }
+    };
+
+    let mut vars = Vec::<Var>::new();
+    let mut buses = HashMap::<&str, usize>::new();
+    for name in names {
+        let values = data
+            .signal(name)
+            .ok_or_else(|| anyhow!("no signal named `{name}`"))?;
+        if values.len() != time.len() {
+            bail!("signal `{name}` does not have the same number of points as time");
+        }
+        let events = options.threshold.digitize(time, values);
+        match bus_bit(name) {
+            Some((bus, index)) => {
+                let i = *buses.entry(bus).or_insert_with(|| {
+                    vars.push(Var {
+                        name: bus.to_string(),
+                        bus: true,
+                        bits: Vec::new(),
+                    });
+                    vars.len() - 1
+                });
+                vars[i].bits.push((index, events));
+            }
+            None => vars.push(Var {
+                name: strip_probe(name).to_string(),
+                bus: false,
+                bits: vec![(0, events)],
+            }),
+        }
+    }
+    for var in vars.iter_mut().filter(|var| var.bus) {
+        var.bits.sort_by_key(|(index, _)| std::cmp::Reverse(*index));
+        for w in var.bits.windows(2) {
+            let (msb, lsb) = (w[0].0, w[1].0);
+            if msb == lsb {
+                bail!("bus `{}` has more than one signal for bit {msb}", var.name);
+            }
+            if msb != lsb + 1 {
+                bail!("bus `{}` is missing bit {}", var.name, lsb + 1);
+            }
+        }
+    }
+
+    // Collect the changes of all variables, in time order.
+    // Only the last change of a variable within a time step is kept.
+    let mut changes = Vec::<(u64, usize, String)>::new();
+    for (i, var) in vars.iter().enumerate() {
+        let first = changes.len();
+        for (t, value) in var.changes() {
+            let tick = (t / options.timescale).round();
+            if tick < 0. {
+                bail!("VCD files cannot contain negative times");
+            }
+            let tick = tick as u64;
+            if changes.len() > first && changes.last().unwrap().0 == tick {
+                changes.pop();
+            }
+            if changes.len() == first || changes.last().unwrap().2 != value {
+                changes.push((tick, i, value));
+            }
+        }
+    }
+    changes.sort_by_key(|(tick, _, _)| *tick);
+
+    let ids = (0..vars.len()).map(identifier).collect::<Vec<_>>();
+    let mut w = BufWriter::new(w);
+    writeln!(
+        w,
+        "$version {} {} $end",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION")
+    )?;
+    writeln!(w, "$timescale {timescale} $end")?;
+    writeln!(w, "$scope module {} $end", options.scope)?;
+    for (var, id) in vars.iter().zip(ids.iter()) {
+        writeln!(w, "{}", var.declaration(id))?;
+    }
+    writeln!(w, "$upscope $end")?;
+    writeln!(w, "$enddefinitions $end")?;
+
+    // The changes at the first time step are the initial values of all variables.
+    for (step, group) in changes.chunk_by(|a, b| a.0 == b.0).enumerate() {
+        writeln!(w, "#{}", group[0].0)?;
+        if step == 0 {
+            writeln!(w, "$dumpvars")?;
+        }
+        for (_, i, value) in group {
+            writeln!(w, "{}", vars[*i].value(value, &ids[*i]))?;
+        }
+        if step == 0 {
+            writeln!(w, "$end")?;
+        }
+    }
+    w.flush()?;
+    Ok(())
+}
+
+/// Formats a timescale in seconds as a VCD timescale, such as `10ps`.
+fn timescale(seconds: f64) -> Result<String> {
+    let units = [
+        ("s", 1.),
+        ("ms", 1e-3),
+        ("us", 1e-6),
+        ("ns", 1e-9),
+        ("ps", 1e-12),
+        ("fs", 1e-15),
+    ];
+    for (unit, scale) in units {
+        for mag in [1, 10, 100] {
+            if float_eq!(seconds, mag as f64 * scale, r2nd <= 1e-9) {
+                return Ok(format!("{mag}{unit}"));
+            }
+        }
+    }
+    bail!("invalid VCD timescale {seconds} s");
+}
+
+/// Generates the short identifier code of the `i`th variable.
+fn identifier(mut i: usize) -> String {
+    let mut id = String::new();
+    loop {
+        id.push((b'!' + (i % 94) as u8) as char);
+        i /= 94;
+        if i == 0 {
+            return id;
+        }
+        i -= 1;
+    }
+}
@@ -3,6 +3,13 @@ use std::cmp::Ordering;
 pub mod analysis;
 pub mod ascii;
 pub mod binary;
+#[cfg(feature = "capi")]
+pub mod capi;
+pub mod export;
+#[cfg(feature = "python")]
+mod python;
+pub mod raw;
+pub mod results;
 
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,183 @@
+//! Python bindings, compatible with the parts of the `psf_utils` API used to plot waveforms.
+//!
+//! ```python
+//! from psfparser import PSF
+//!
+//! psf = PSF('timeSweep.tran.tran')
+//! sweep = psf.get_sweep()
+//! out = psf.get_signal('v(dout[0])')
+//! print(sweep.name, sweep.units, sweep.abscissa, out.ordinate)
+//! ```
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use numpy::IntoPyArray;
+use pyo3::exceptions::{PyKeyError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyFloat;
+
+use crate::analysis::dc::DcData;
+use crate::results::{Analysis, PsfFile};
+
+#[cfg(test)]
+mod tests;
+
+/// A PSF file containing a transient, AC or DC analysis.
+#[pyclass(name = "PSF", module = "psfparser", frozen)]
+struct Psf {
+    file: PsfFile,
+}
+
+/// The sweep variable of an analysis.
+#[pyclass(module = "psfparser", frozen, get_all)]
+struct Sweep {
+    name: String,
+    units: String,
+    /// The swept values, as a NumPy array.
+    abscissa: Py<PyAny>,
+}
+
+/// A signal in an analysis.
+#[pyclass(module = "psfparser", frozen, get_all)]
+struct Signal {
+    name: String,
+    units: String,
+    /// The signal values, as a NumPy array of floats or complex numbers.
+    /// The value of an operating point signal is a float.
+    ordinate: Py<PyAny>,
+}
+
+fn units(units: &HashMap<String, String>, name: &str) -> String {
+    units.get(name).cloned().unwrap_or_default()
+}
+
+#[pymethods]
+impl Psf {
+    #[new]
+    fn new(filename: PathBuf) -> PyResult<Self> {
+        let file = PsfFile::open(filename)?;
+        match file.analysis {
+            Analysis::Tran(_) | Analysis::Ac(_) | Analysis::Dc(_) => Ok(Self { file }),
+            _ => Err(PyValueError::new_err(format!(
+                "unsupported analysis type `{}`",
+                file.header["analysis type"]
+            ))),
+        }
+    }
+
+    /// The header values of the file.
+    #[getter]
+    fn header(&self) -> HashMap<String, String> {
+        self.file.header.clone()
+    }
+
+    /// Gets the sweep variable, or `None` for an operating point.
+    fn get_sweep(&self, py: Python<'_>) -> Option<Sweep> {
+        let (name, units, values) = match &self.file.analysis {
+            Analysis::Tran(data) => (
+                data.time.clone(),
+                units(&data.units, &data.time),
+                data.signal(&data.time)?.clone(),
+            ),
+            Analysis::Ac(data) => (
+                "freq".to_string(),
+                data.units
+                    .get("freq")
+                    .map_or("Hz", String::as_str)
+                    .to_string(),
+                data.freq.clone(),
+            ),
+            Analysis::Dc(DcData::Sweep(data)) => (
+                data.param.0.clone(),
+                units(&data.units, &data.param.0),
+                data.param.1.clone(),
+            ),
+            _ => return None,
+        };
+        Some(Sweep {
+            name,
+            units,
+            abscissa: values.into_pyarray(py).into_any().unbind(),
+        })
+    }
+
+    /// Gets the signal named `name`.
+    fn get_signal(&self, py: Python<'_>, name: &str) -> PyResult<Signal> {
+        let ordinate = match &self.file.analysis {
+            Analysis::Tran(data) => data
+                .signal(name)
+                .map(|values| values.clone().into_pyarray(py).into_any().unbind()),
+            Analysis::Ac(data) => data
+                .signal(name)
+                .map(|values| values.clone().into_pyarray(py).into_any().unbind()),
+            Analysis::Dc(DcData::Sweep(data)) => data
+                .signal(name)
+                .map(|values| values.clone().into_pyarray(py).into_any().unbind()),
+            Analysis::Dc(DcData::Op(data)) => data
+                .signal(name)
+                .map(|value| PyFloat::new(py, value).into_any().unbind()),
+            _ => None,
+        };
+        let ordinate = ordinate.ok_or_else(|| PyKeyError::new_err(name.to_string()))?;
+        Ok(Signal {
+            name: name.to_string(),
+            units: units(self.units(), name),
+            ordinate,
+        })
+    }
+
+    /// Gets all signals other than the sweep variable, sorted by name.
+    fn all_signals(&self, py: Python<'_>) -> PyResult<Vec<Signal>> {
+        let mut names = match &self.file.analysis {
+            Analysis::Tran(data) => data
+                .signals
+                .keys()
+                .filter(|name| **name != data.time)
+                .collect(),
+            Analysis::Ac(data) => data.signals.keys().collect(),
+            Analysis::Dc(DcData::Sweep(data)) => data.signals.keys().collect(),
+            Analysis::Dc(DcData::Op(data)) => data.signals.keys().collect(),
+            _ => Vec::new(),
+        };
+        names.sort_unstable();
+        names
+            .into_iter()
+            .map(|name| self.get_signal(py, name))
+            .collect()
+    }
+
+    /// Converts units to a form suitable for display, such as `Ohm` to `Ω`.
+    #[staticmethod]
+    fn units_to_unicode(units: &str) -> String {
+        if units == "NONE" {
+            return String::new();
+        }
+        units
+            .replace("sqrt(Hz)", "√Hz")
+            .replace("Ohm", "Ω")
+            .replace("^2", "²")
+    }
+}
+
+impl Psf {
+    fn units(&self) -> &HashMap<String, String> {
+        match &self.file.analysis {
+            Analysis::Tran(data) => &data.units,
+            Analysis::Ac(data) => &data.units,
+            Analysis::Dc(DcData::Sweep(data)) => &data.units,
+            Analysis::Dc(DcData::Op(data)) => &data.units,
+            _ => unreachable!("unsupported analyses are rejected when opened"),
+        }
+    }
+}
+
+#[pymodule]
+fn psfparser(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    // Fail on import rather than on first use if NumPy is missing.
+    m.py().import("numpy")?;
+    m.add_class::<Psf>()?;
+    m.add_class::<Sweep>()?;
+    m.add_class::<Signal>()?;
+    Ok(())
+}
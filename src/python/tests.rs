@@ -0,0 +1,72 @@
+use std::path::{Path, PathBuf};
+
+use pyo3::exceptions::{PyKeyError, PyValueError};
+use pyo3::prelude::*;
+
+use super::Psf;
+use crate::analysis::dc::DcData;
+use crate::results::Analysis;
+
+fn example(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("examples")
+        .join(name)
+}
+
+#[test]
+fn opens_supported_analyses() {
+    let psf = Psf::new(example("vdiv_sin_bin.tran.tran")).unwrap();
+    assert_eq!(psf.header()["analysis type"], "tran");
+    assert_eq!(psf.units()["time"], "s");
+
+    let psf = Psf::new(example("frequencySweep.ac")).unwrap();
+    assert!(matches!(psf.file.analysis, Analysis::Ac(_)));
+    assert_eq!(psf.header()["analysis type"], "ac");
+
+    let psf = Psf::new(example("dc2.dc")).unwrap();
+    let Analysis::Dc(DcData::Op(data)) = &psf.file.analysis else {
+        panic!("expected a dc operating point");
+    };
+    let (name, value) = data.signals.iter().min_by_key(|(name, _)| *name).unwrap();
+    Python::initialize();
+    Python::attach(|py| {
+        assert!(psf.get_sweep(py).is_none());
+        let signal = psf.get_signal(py, name).unwrap();
+        assert_eq!(signal.ordinate.extract::<f64>(py).unwrap(), *value);
+        let names = psf
+            .all_signals(py)
+            .unwrap()
+            .into_iter()
+            .map(|signal| signal.name)
+            .collect::<Vec<_>>();
+        assert_eq!(names.len(), data.signals.len());
+        assert!(names.is_sorted());
+        let err = psf.get_signal(py, "missing").err().unwrap();
+        assert!(err.is_instance_of::<PyKeyError>(py));
+    });
+}
+
+#[test]
+fn rejects_unsupported_analyses() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("vdiv.pss");
+    let tran = std::fs::read_to_string(example("vdiv_sin_ascii.tran.tran")).unwrap();
+    let pss = tran.replace("\"analysis type\" \"tran\"", "\"analysis type\" \"pss\"");
+    std::fs::write(&path, pss).unwrap();
+
+    let err = Psf::new(path).err().unwrap();
+    Python::initialize();
+    Python::attach(|py| {
+        assert!(err.is_instance_of::<PyValueError>(py));
+        assert!(err.to_string().contains("unsupported analysis type `pss`"));
+    });
+    assert!(Psf::new(example("missing.tran")).is_err());
+}
+
+#[test]
+fn converts_units_to_unicode() {
+    assert_eq!(Psf::units_to_unicode("V/sqrt(Hz)"), "V/√Hz");
+    assert_eq!(Psf::units_to_unicode("Ohm"), "Ω");
+    assert_eq!(Psf::units_to_unicode("A^2/Hz"), "A²/Hz");
+    assert_eq!(Psf::units_to_unicode("NONE"), "");
+}
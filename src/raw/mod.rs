@@ -0,0 +1,450 @@
+//! SPICE raw (nutmeg) files, as written by ngspice, Xyce and other SPICE simulators.
+//!
+//! A raw file contains one or more plots, each consisting of a text header
+//! listing its variables, followed by the values of every variable at each point
+//! in either ASCII (`Values:`) or binary (`Binary:`) form.
+//! Binary values are stored as little-endian doubles.
+//!
+//! Plots can also be created from transient and AC results and written in either form,
+//! for use with ngspice and open-source waveform viewers.
+
+use std::io::{BufWriter, Write};
+
+use anyhow::{anyhow, bail};
+use num::complex::Complex64;
+
+use crate::analysis::ac::AcData;
+use crate::analysis::transient::TransientData;
+use crate::Result;
+
+#[cfg(test)]
+mod tests;
+
+/// A single plot in a raw file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plot {
+    pub title: String,
+    pub date: String,
+    /// The plot name, such as `Transient Analysis` or `AC Analysis`.
+    pub name: String,
+    /// The plot variables, starting with the sweep variable if the plot has one.
+    pub variables: Vec<Variable>,
+    pub values: RawValues,
+}
+
+/// A variable in a raw file plot.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Variable {
+    pub name: String,
+    /// The variable type, such as `time`, `voltage` or `current`.
+    pub kind: String,
+}
+
+/// The encoding of the values in a raw file.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub enum RawFormat {
+    Ascii,
+    #[default]
+    Binary,
+}
+
+/// The values of each variable in a plot, indexed by variable then by point.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RawValues {
+    Real(Vec<Vec<f64>>),
+    Complex(Vec<Vec<Complex64>>),
+}
+
+impl Variable {
+    /// The units implied by the variable type, if it is a known type.
+    pub fn units(&self) -> Option<&'static str> {
+        Some(match self.kind.as_str() {
+            "time" => "s",
+            "frequency" => "Hz",
+            "voltage" => "V",
+            "current" => "A",
+            _ => return None,
+        })
+    }
+}
+
+impl Plot {
+    /// The number of points in the plot.
+    pub fn points(&self) -> usize {
+        match self.values {
+            RawValues::Real(ref values) => values.first().map_or(0, Vec::len),
+            RawValues::Complex(ref values) => values.first().map_or(0, Vec::len),
+        }
+    }
+
+    #[inline]
+    pub fn is_complex(&self) -> bool {
+        matches!(self.values, RawValues::Complex(_))
+    }
+
+    /// Creates a transient analysis plot, with time as the first variable
+    /// followed by the remaining signals sorted by name.
+    ///
+    /// Variable types are inferred from the signal units.
+    /// The title and date are left empty.
+    pub fn from_transient(data: &TransientData) -> Result<Self> {
+        let time = data
+            .signal(&data.time)
+            .ok_or_else(|| anyhow!("missing time signal `{}`", data.time))?;
+        let mut names = data
+            .signals
+            .keys()
+            .filter(|name| **name != data.time)
+            .collect::<Vec<_>>();
+        names.sort_unstable();
+
+        let mut variables = vec![Variable {
+            name: data.time.clone(),
+            kind: "time".to_string(),
+        }];
+        let mut values = vec![time.clone()];
+        for name in names {
+            variables.push(Variable {
+                name: name.clone(),
+                kind: kind_for_units(data.units.get(name)).to_string(),
+            });
+            values.push(data.signals[name].clone());
+        }
+
+        Ok(Self {
+            title: String::new(),
+            date: String::new(),
+            name: "Transient Analysis".to_string(),
+            variables,
+            values: RawValues::Real(values),
+        })
+    }
+
+    /// Creates an AC analysis plot, with frequency as the first variable
+    /// followed by the signals sorted by name.
+    ///
+    /// Variable types are inferred from the signal units.
+    /// The title and date are left empty.
+    pub fn from_ac(data: &AcData) -> Self {
+        let mut names = data.signals.keys().collect::<Vec<_>>();
+        names.sort_unstable();
+
+        let mut variables = vec![Variable {
+            name: "frequency".to_string(),
+            kind: "frequency".to_string(),
+        }];
+        let mut values = vec![data.freq.iter().map(|&f| Complex64::new(f, 0.)).collect()];
+        for name in names {
+            variables.push(Variable {
+                name: name.clone(),
+                kind: kind_for_units(data.units.get(name)).to_string(),
+            });
+            values.push(data.signals[name].clone());
+        }
+
+        Self {
+            title: String::new(),
+            date: String::new(),
+            name: "AC Analysis".to_string(),
+            variables,
+            values: RawValues::Complex(values),
+        }
+    }
+
+    /// Writes the plot in raw file format.
+    ///
+    /// Fails if the plot does not have exactly one value per variable at every point.
+    pub fn write(&self, w: impl Write, format: RawFormat) -> Result<()> {
+        let points = self.points();
+        let lengths = match self.values {
+            RawValues::Real(ref values) => values.iter().map(Vec::len).collect::<Vec<_>>(),
+            RawValues::Complex(ref values) => values.iter().map(Vec::len).collect(),
+        };
+        if lengths.len() != self.variables.len() || lengths.iter().any(|&n| n != points) {
+            bail!("plot values do not match its variables");
+        }
+
+        let mut w = BufWriter::new(w);
+        writeln!(w, "Title: {}", self.title)?;
+        writeln!(w, "Date: {}", self.date)?;
+        writeln!(w, "Plotname: {}", self.name)?;
+        let flags = if self.is_complex() { "complex" } else { "real" };
+        writeln!(w, "Flags: {flags}")?;
+        writeln!(w, "No. Variables: {}", self.variables.len())?;
+        writeln!(w, "No. Points: {points}")?;
+        writeln!(w, "Variables:")?;
+        for (i, var) in self.variables.iter().enumerate() {
+            writeln!(w, "\t{i}\t{}\t{}", var.name, var.kind)?;
+        }
+
+        match format {
+            RawFormat::Ascii => {
+                writeln!(w, "Values:")?;
+                for i in 0..points {
+                    write!(w, " {i}")?;
+                    match self.values {
+                        RawValues::Real(ref values) => {
+                            for var in values.iter() {
+                                writeln!(w, "\t{:e}", var[i])?;
+                            }
+                        }
+                        RawValues::Complex(ref values) => {
+                            for var in values.iter() {
+                                writeln!(w, "\t{:e},{:e}", var[i].re, var[i].im)?;
+                            }
+                        }
+                    }
+                }
+            }
+            RawFormat::Binary => {
+                writeln!(w, "Binary:")?;
+                for i in 0..points {
+                    match self.values {
+                        RawValues::Real(ref values) => {
+                            for var in values.iter() {
+                                w.write_all(&var[i].to_le_bytes())?;
+                            }
+                        }
+                        RawValues::Complex(ref values) => {
+                            for var in values.iter() {
+                                w.write_all(&var[i].re.to_le_bytes())?;
+                                w.write_all(&var[i].im.to_le_bytes())?;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        w.flush()?;
+        Ok(())
+    }
+}
+
+/// Writes several plots to a single raw file.
+pub fn write(mut w: impl Write, plots: &[Plot], format: RawFormat) -> Result<()> {
+    for plot in plots {
+        plot.write(&mut w, format)?;
+    }
+    Ok(())
+}
+
+/// The raw file variable type for a signal with the given units.
+fn kind_for_units(units: Option<&String>) -> &'static str {
+    match units.map(String::as_str) {
+        Some("s") => "time",
+        Some("Hz") => "frequency",
+        Some("V") => "voltage",
+        Some("A") => "current",
+        _ => "notype",
+    }
+}
+
+/// Parses every plot in a raw file.
+///
+/// Plots whose values are cut short, for example because the simulation is still
+/// running, contain only their complete points.
+pub fn parse(input: &[u8]) -> Result<Vec<Plot>> {
+    let mut parser = RawParser { data: input };
+    let mut plots = Vec::new();
+    while !parser.at_end() {
+        plots.push(parser.parse_plot()?);
+    }
+    Ok(plots)
+}
+
+struct RawParser<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> RawParser<'a> {
+    fn at_end(&self) -> bool {
+        self.data.iter().all(u8::is_ascii_whitespace)
+    }
+
+    fn line_end(&self) -> usize {
+        self.data
+            .iter()
+            .position(|&b| b == b'\n')
+            .unwrap_or(self.data.len())
+    }
+
+    fn peek_line(&self) -> &'a str {
+        to_str(&self.data[..self.line_end()]).trim_end_matches('\r')
+    }
+
+    fn next_line(&mut self) -> Option<&'a str> {
+        if self.data.is_empty() {
+            return None;
+        }
+        let line = self.peek_line();
+        self.data = &self.data[(self.line_end() + 1).min(self.data.len())..];
+        Some(line)
+    }
+
+    fn parse_plot(&mut self) -> Result<Plot> {
+        let (mut title, mut date, mut name) = (String::new(), String::new(), String::new());
+        let mut complex = false;
+        let mut num_vars = None;
+        let mut num_points = None;
+        let mut variables = Vec::new();
+
+        loop {
+            let line = self
+                .next_line()
+                .ok_or_else(|| anyhow!("unexpected end of raw file header"))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let (key, value) = line
+                .split_once(':')
+                .ok_or_else(|| anyhow!("malformed raw file header line: {line}"))?;
+            let value = value.trim();
+            match key.trim() {
+                "Title" => title = value.to_string(),
+                "Date" => date = value.to_string(),
+                "Plotname" => name = value.to_string(),
+                "Flags" => complex = value.split_whitespace().any(|flag| flag == "complex"),
+                "No. Variables" => num_vars = Some(value.parse::<usize>()?),
+                "No. Points" => num_points = Some(value.parse::<usize>()?),
+                "Variables" => {
+                    let n = num_vars.ok_or_else(|| anyhow!("variables listed before count"))?;
+                    let mut first = (!value.is_empty()).then_some(value);
+                    for _ in 0..n {
+                        let line = match first.take() {
+                            Some(line) => line,
+                            None => self
+                                .next_line()
+                                .ok_or_else(|| anyhow!("unexpected end of variable list"))?,
+                        };
+                        variables.push(parse_variable(line)?);
+                    }
+                }
+                "Values" => {
+                    let values = self.parse_ascii_values(variables.len(), num_points, complex)?;
+                    return Ok(Plot {
+                        title,
+                        date,
+                        name,
+                        variables,
+                        values,
+                    });
+                }
+                "Binary" => {
+                    let values = self.parse_binary_values(variables.len(), num_points, complex);
+                    return Ok(Plot {
+                        title,
+                        date,
+                        name,
+                        variables,
+                        values,
+                    });
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn parse_ascii_values(
+        &mut self,
+        num_vars: usize,
+        num_points: Option<usize>,
+        complex: bool,
+    ) -> Result<RawValues> {
+        // Each point is an index followed by the value of every variable.
+        let mut tokens = Vec::new();
+        let limit = num_points.map(|n| n * (num_vars + 1));
+        while limit.is_none_or(|limit| tokens.len() < limit) {
+            let line = self.peek_line();
+            if line.starts_with(|c: char| c.is_ascii_alphabetic()) && line.contains(':') {
+                // The header of the next plot.
+                break;
+            }
+            match self.next_line() {
+                Some(line) => tokens.extend(line.split_whitespace()),
+                None => break,
+            }
+        }
+
+        let points = tokens.chunks_exact(num_vars + 1);
+        if complex {
+            let mut values = vec![Vec::new(); num_vars];
+            for point in points {
+                for (var, token) in values.iter_mut().zip(&point[1..]) {
+                    var.push(parse_complex(token)?);
+                }
+            }
+            Ok(RawValues::Complex(values))
+        } else {
+            let mut values = vec![Vec::new(); num_vars];
+            for point in points {
+                for (var, token) in values.iter_mut().zip(&point[1..]) {
+                    var.push(token.parse::<f64>()?);
+                }
+            }
+            Ok(RawValues::Real(values))
+        }
+    }
+
+    fn parse_binary_values(
+        &mut self,
+        num_vars: usize,
+        num_points: Option<usize>,
+        complex: bool,
+    ) -> RawValues {
+        let width = if complex { 16 } else { 8 };
+        let point_len = num_vars * width;
+        let available = self.data.len() / point_len.max(1);
+        let n = num_points.map_or(available, |n| n.min(available));
+
+        let (data, rest) = self.data.split_at(n * point_len);
+        // Only a complete plot can be followed by another one.
+        self.data = if num_points == Some(n) { rest } else { &[] };
+
+        let mut doubles = data
+            .chunks_exact(8)
+            .map(|chunk| f64::from_le_bytes(chunk.try_into().unwrap()));
+        if complex {
+            let mut values = vec![Vec::with_capacity(n); num_vars];
+            for _ in 0..n {
+                for var in values.iter_mut() {
+                    let re = doubles.next().unwrap();
+                    let im = doubles.next().unwrap();
+                    var.push(Complex64::new(re, im));
+                }
+            }
+            RawValues::Complex(values)
+        } else {
+            let mut values = vec![Vec::with_capacity(n); num_vars];
+            for _ in 0..n {
+                for var in values.iter_mut() {
+                    var.push(doubles.next().unwrap());
+                }
+            }
+            RawValues::Real(values)
+        }
+    }
+}
+
+/// Interprets header text, which is treated as empty if it is not valid UTF-8.
+fn to_str(data: &[u8]) -> &str {
+    std::str::from_utf8(data).unwrap_or("")
+}
+
+fn parse_variable(line: &str) -> Result<Variable> {
+    let mut fields = line.split_whitespace();
+    let (Some(_index), Some(name), Some(kind)) = (fields.next(), fields.next(), fields.next())
+    else {
+        bail!("malformed raw file variable: {line}");
+    };
+    Ok(Variable {
+        name: name.to_string(),
+        kind: kind.to_string(),
+    })
+}
+
+fn parse_complex(token: &str) -> Result<Complex64> {
+    let (re, im) = token
+        .split_once(',')
+        .ok_or_else(|| anyhow!("expected complex value; found {token}"))?;
+    Ok(Complex64::new(re.parse()?, im.parse()?))
+}
@@ -0,0 +1,204 @@
+use num::complex::Complex64;
+
+use crate::analysis::ac::AcData;
+use crate::analysis::dc::DcData;
+use crate::analysis::transient::TransientData;
+
+use super::*;
+
+static TRAN_ASCII: &str = "Title: * rc lowpass
+Date: Sun May 14 15:05:37  2023
+Plotname: Transient Analysis
+Flags: real
+No. Variables: 3
+No. Points: 3
+Variables:
+\t0\ttime\ttime
+\t1\tv(out)\tvoltage
+\t2\ti(v1)\tcurrent
+Values:
+ 0\t0.000000000000000e+00
+\t0.000000000000000e+00
+\t-1.000000000000000e-03
+ 1\t1.000000000000000e-09
+\t5.000000000000000e-01
+\t-5.000000000000000e-04
+ 2\t2.000000000000000e-09
+\t7.500000000000000e-01
+\t-2.500000000000000e-04
+";
+
+static AC_ASCII: &str = "Title: * rc lowpass
+Date: Sun May 14 15:05:37  2023
+Plotname: AC Analysis
+Flags: complex
+No. Variables: 2
+No. Points: 2
+Variables:
+\t0\tfrequency\tfrequency grid=3
+\t1\tv(out)\tvoltage
+Values:
+ 0\t1.000000000000000e+03,0.000000000000000e+00
+\t9.999999999999999e-01,-1.000000000000000e-03
+ 1\t1.000000000000000e+06,0.000000000000000e+00
+\t5.000000000000000e-01,-5.000000000000000e-01
+";
+
+static OP_ASCII: &str = "Title: * divider
+Date: Sun May 14 15:05:37  2023
+Plotname: Operating Point
+Flags: real
+No. Variables: 2
+No. Points: 1
+Variables:
+\t0\tv(out)\tvoltage
+\t1\ti(v1)\tcurrent
+Values:
+ 0\t4.500000000000000e-01
+\t-9.000000000000000e-04
+";
+
+/// Builds a binary raw file with the given header and little-endian values.
+fn binary_raw(header: &str, values: &[f64]) -> Vec<u8> {
+    let mut data = header.as_bytes().to_vec();
+    data.extend_from_slice(b"Binary:\n");
+    for v in values {
+        data.extend_from_slice(&v.to_le_bytes());
+    }
+    data
+}
+
+#[test]
+fn parses_ascii_transient() {
+    let plots = parse(TRAN_ASCII.as_bytes()).unwrap();
+    assert_eq!(plots.len(), 1);
+    let plot = &plots[0];
+    assert_eq!(plot.name, "Transient Analysis");
+    assert_eq!(plot.points(), 3);
+    assert_eq!(plot.variables[2].kind, "current");
+
+    let data = TransientData::from_raw(plot.clone()).unwrap();
+    assert_eq!(data.time, "time");
+    assert_eq!(data.signal("time").unwrap(), &vec![0., 1e-9, 2e-9]);
+    assert_eq!(data.signal("v(out)").unwrap(), &vec![0., 0.5, 0.75]);
+    assert_eq!(data.units["i(v1)"], "A");
+    assert!(AcData::from_raw(plot.clone()).is_err());
+}
+
+#[test]
+fn parses_ascii_ac() {
+    let plots = parse(AC_ASCII.as_bytes()).unwrap();
+    let data = AcData::from_raw(plots[0].clone()).unwrap();
+    assert_eq!(data.freq, vec![1e3, 1e6]);
+    assert_eq!(data.signal("v(out)").unwrap()[1], Complex64::new(0.5, -0.5));
+    assert_eq!(data.units["freq"], "Hz");
+}
+
+#[test]
+fn parses_multiple_plots() {
+    let input = format!("{OP_ASCII}{TRAN_ASCII}");
+    let plots = parse(input.as_bytes()).unwrap();
+    assert_eq!(plots.len(), 2);
+
+    let DcData::Op(op) = DcData::from_raw(plots[0].clone()).unwrap() else {
+        panic!("expected an operating point");
+    };
+    assert_eq!(op.signal("v(out)"), Some(0.45));
+
+    let DcData::Sweep(sweep) = DcData::from_raw(plots[1].clone()).unwrap() else {
+        panic!("expected a sweep");
+    };
+    assert_eq!(sweep.param.0, "time");
+    assert_eq!(sweep.signals.len(), 2);
+}
+
+#[test]
+fn parses_binary() {
+    let header = "Title: * rc lowpass
+Date: Sun May 14 15:05:37  2023
+Plotname: Transient Analysis
+Flags: real
+No. Variables: 2
+No. Points: 3
+Variables:
+\t0\ttime\ttime
+\t1\tv(out)\tvoltage
+";
+    let values = [0., 0., 1e-9, 0.5, 2e-9, 0.75];
+    let plots = parse(&binary_raw(header, &values)).unwrap();
+    let data = TransientData::from_raw(plots[0].clone()).unwrap();
+    assert_eq!(data.signal("v(out)").unwrap(), &vec![0., 0.5, 0.75]);
+
+    // A file that is still being written contains only complete points.
+    let mut partial = binary_raw(header, &values[..5]);
+    partial.extend_from_slice(&[0; 3]);
+    let plots = parse(&partial).unwrap();
+    assert_eq!(plots[0].points(), 2);
+
+    let header = "Title: * rc lowpass
+Date: Sun May 14 15:05:37  2023
+Plotname: AC Analysis
+Flags: complex
+No. Variables: 2
+No. Points: 1
+Variables:
+\t0\tfrequency\tfrequency
+\t1\tv(out)\tvoltage
+";
+    let mut input = binary_raw(header, &[1e3, 0., 0.5, -0.5]);
+    input.extend_from_slice(OP_ASCII.as_bytes());
+    let plots = parse(&input).unwrap();
+    assert_eq!(plots.len(), 2);
+    let data = AcData::from_raw(plots[0].clone()).unwrap();
+    assert_eq!(data.freq, vec![1e3]);
+    assert_eq!(data.signal("v(out)").unwrap()[0], Complex64::new(0.5, -0.5));
+    assert_eq!(plots[1].name, "Operating Point");
+}
+
+fn round_trip(plot: &Plot, format: RawFormat) -> Plot {
+    let mut out = Vec::new();
+    plot.write(&mut out, format).unwrap();
+    let mut plots = parse(&out).unwrap();
+    assert_eq!(plots.len(), 1);
+    plots.remove(0)
+}
+
+#[test]
+fn round_trips_transient() {
+    use crate::ascii::parse as ascii_parse;
+    use crate::ascii::tests::VDIV_SIN_PSF;
+
+    let data = TransientData::from_ascii(&ascii_parse(VDIV_SIN_PSF).unwrap());
+    let plot = Plot::from_transient(&data).unwrap();
+    assert_eq!(plot.variables[0].name, "time");
+    for format in [RawFormat::Ascii, RawFormat::Binary] {
+        let parsed = round_trip(&plot, format);
+        assert_eq!(parsed, plot);
+        let read = TransientData::from_raw(parsed).unwrap();
+        assert_eq!(read.signals, data.signals);
+        assert_eq!(read.units["time"], "s");
+    }
+}
+
+#[test]
+fn round_trips_ac() {
+    use crate::ascii::parse as ascii_parse;
+    use crate::ascii::tests::AC_EXAMPLE_PSF;
+    use crate::binary::parse as bin_parse;
+    use crate::binary::tests::AC_ZOUT_PSFBIN;
+
+    let ascii = AcData::from_ascii(&ascii_parse(AC_EXAMPLE_PSF).unwrap());
+    let binary = AcData::from_binary(bin_parse(AC_ZOUT_PSFBIN).unwrap());
+    for data in [ascii, binary] {
+        let plot = Plot::from_ac(&data);
+        let mut plots = Vec::new();
+        write(&mut plots, &[plot.clone(), plot.clone()], RawFormat::Binary).unwrap();
+        assert_eq!(parse(&plots).unwrap().len(), 2);
+
+        for format in [RawFormat::Ascii, RawFormat::Binary] {
+            let read = AcData::from_raw(round_trip(&plot, format)).unwrap();
+            assert_eq!(read.freq, data.freq);
+            assert_eq!(read.signals, data.signals);
+        }
+    }
+}
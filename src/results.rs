@@ -0,0 +1,262 @@
+use std::cell::OnceCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail};
+
+use crate::analysis::ac::AcData;
+use crate::analysis::dc::DcData;
+use crate::analysis::noise::NoiseData;
+use crate::analysis::pac::{PnoiseData, SidebandData};
+use crate::analysis::pss::{PssFreqData, PssTimeData};
+use crate::analysis::sp::SpData;
+use crate::analysis::stb::StbData;
+use crate::analysis::transient::TransientData;
+use crate::analysis::xf::XfData;
+use crate::analysis::{is_ascii, FromPsf};
+use crate::ascii::ast::{Field, PsfAst as AsciiAst, Values};
+use crate::binary::ast::PsfAst as BinaryAst;
+use crate::Result;
+
+/// The name of the file listing the analyses in a results directory.
+pub const LOG_FILE: &str = "logFile";
+
+/// A Spectre results (`psf/`) directory.
+///
+/// Analyses are listed from the directory's `logFile`
+/// and are only parsed when first requested.
+#[derive(Debug)]
+pub struct ResultsDir {
+    root: PathBuf,
+    analyses: Vec<AnalysisInfo>,
+    cache: Vec<OnceCell<Analysis>>,
+}
+
+/// A single PSF file, read without a results directory.
+#[derive(Debug)]
+pub struct PsfFile {
+    /// The header values of the file, formatted as strings.
+    pub header: HashMap<String, String>,
+    /// The data in the file, interpreted according to its `analysis type` header value.
+    pub analysis: Analysis,
+}
+
+/// An analysis listed in a results directory's `logFile`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnalysisInfo {
+    /// The instance name of the analysis, e.g. `tran1`.
+    pub name: String,
+    /// The type of the analysis, e.g. `tran`.
+    pub analysis_type: String,
+    /// The path to the analysis's data file.
+    pub data_file: PathBuf,
+    /// The format of the data file, e.g. `PSF`.
+    pub format: String,
+    /// The name of the parent analysis, if any.
+    pub parent: Option<String>,
+    pub description: String,
+}
+
+/// The data produced by an analysis.
+#[derive(Debug)]
+pub enum Analysis {
+    Tran(TransientData),
+    Ac(AcData),
+    Dc(DcData),
+    Noise(NoiseData),
+    Stb(StbData),
+    Sp(SpData),
+    Xf(XfData),
+    PssTime(PssTimeData),
+    PssFreq(PssFreqData),
+    Pac(SidebandData),
+    Pxf(SidebandData),
+    Pnoise(PnoiseData),
+}
+
+impl ResultsDir {
+    /// Opens a results directory, reading its `logFile`.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let root = path.as_ref().to_path_buf();
+        let log = std::fs::read_to_string(root.join(LOG_FILE))?;
+        let ast = crate::ascii::parse(&log)?;
+
+        let mut analyses = Vec::new();
+        for v in ast.values.iter() {
+            let Values::Mixed(fields) = &v.values else {
+                continue;
+            };
+            let field = |i: usize| fields.get(i).and_then(Field::str).unwrap_or_default();
+            let parent = field(3);
+            analyses.push(AnalysisInfo {
+                name: v.signal.to_string(),
+                analysis_type: field(0).to_string(),
+                data_file: root.join(field(1)),
+                format: field(2).to_string(),
+                parent: (!parent.is_empty()).then(|| parent.to_string()),
+                description: fields
+                    .iter()
+                    .skip(4)
+                    .find_map(Field::str)
+                    .unwrap_or_default()
+                    .to_string(),
+            });
+        }
+
+        let cache = analyses.iter().map(|_| OnceCell::new()).collect();
+        Ok(Self {
+            root,
+            analyses,
+            cache,
+        })
+    }
+
+    /// The path to the results directory.
+    #[inline]
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The analyses listed in the `logFile`, in order.
+    #[inline]
+    pub fn analyses(&self) -> &[AnalysisInfo] {
+        &self.analyses
+    }
+
+    /// Gets the listing of the analysis named `name`.
+    pub fn info(&self, name: &str) -> Option<&AnalysisInfo> {
+        self.analyses.iter().find(|a| a.name == name)
+    }
+
+    /// Lists the analyses of the given type.
+    pub fn analyses_of_type<'a>(
+        &'a self,
+        analysis_type: &'a str,
+    ) -> impl Iterator<Item = &'a AnalysisInfo> + 'a {
+        self.analyses
+            .iter()
+            .filter(move |a| a.analysis_type == analysis_type)
+    }
+
+    /// Gets the data of the analysis named `name`, parsing it on first access.
+    pub fn analysis(&self, name: &str) -> Result<&Analysis> {
+        let idx = self
+            .analyses
+            .iter()
+            .position(|a| a.name == name)
+            .ok_or_else(|| anyhow!("no analysis named `{name}`"))?;
+        let cell = &self.cache[idx];
+        if let Some(analysis) = cell.get() {
+            return Ok(analysis);
+        }
+        let analysis = Analysis::load(&self.analyses[idx])?;
+        Ok(cell.get_or_init(|| analysis))
+    }
+
+    /// Parses the analysis named `name` as a specific data type, bypassing the cache.
+    pub fn load<T: FromPsf>(&self, name: &str) -> Result<T> {
+        let info = self
+            .info(name)
+            .ok_or_else(|| anyhow!("no analysis named `{name}`"))?;
+        T::from_file(&info.data_file)
+    }
+}
+
+/// A parsed PSF file in either format.
+enum Ast<'a> {
+    Ascii(AsciiAst<'a>),
+    Binary(BinaryAst<'a>),
+}
+
+impl<'a> Ast<'a> {
+    fn parse(data: &'a [u8]) -> Result<Self> {
+        Ok(if is_ascii(data) {
+            Self::Ascii(crate::ascii::parse(std::str::from_utf8(data)?)?)
+        } else {
+            Self::Binary(crate::binary::parse(data)?)
+        })
+    }
+
+    fn header(&self) -> HashMap<String, String> {
+        match self {
+            Self::Ascii(ast) => ast.header.to_strings(),
+            Self::Binary(ast) => ast.header.to_strings(),
+        }
+    }
+
+    fn data<T: FromPsf>(self) -> Result<T> {
+        match self {
+            Self::Ascii(ast) => T::from_ascii_ast(&ast),
+            Self::Binary(ast) => T::from_binary_ast(ast),
+        }
+    }
+}
+
+impl Analysis {
+    /// Parses the data file of an analysis according to its type.
+    pub fn load(info: &AnalysisInfo) -> Result<Self> {
+        let data = std::fs::read(&info.data_file)?;
+        Self::from_ast(&info.analysis_type, &info.data_file, Ast::parse(&data)?)
+            .map_err(|e| e.context(format!("failed to load analysis `{}`", info.name)))
+    }
+
+    fn from_ast(analysis_type: &str, path: &Path, ast: Ast) -> Result<Self> {
+        Ok(match analysis_type {
+            "tran" => Self::Tran(ast.data()?),
+            "ac" => Self::Ac(ast.data()?),
+            "dc" => Self::Dc(ast.data()?),
+            "noise" => Self::Noise(ast.data()?),
+            "stb" => Self::Stb(ast.data()?),
+            "sp" => Self::Sp(ast.data()?),
+            "xf" => Self::Xf(ast.data()?),
+            "pss" | "hb" => {
+                let file = path.file_name().unwrap_or_default().to_string_lossy();
+                if file.contains(".fd.") {
+                    Self::PssFreq(ast.data()?)
+                } else {
+                    Self::PssTime(ast.data()?)
+                }
+            }
+            "pac" | "hbac" => Self::Pac(ast.data()?),
+            "pxf" | "hbxf" => Self::Pxf(ast.data()?),
+            "pnoise" | "hbnoise" => Self::Pnoise(ast.data()?),
+            t => bail!("unsupported analysis type `{t}`"),
+        })
+    }
+
+    pub fn tran(&self) -> Option<&TransientData> {
+        match self {
+            Self::Tran(data) => Some(data),
+            _ => None,
+        }
+    }
+
+    pub fn ac(&self) -> Option<&AcData> {
+        match self {
+            Self::Ac(data) => Some(data),
+            _ => None,
+        }
+    }
+
+    pub fn dc(&self) -> Option<&DcData> {
+        match self {
+            Self::Dc(data) => Some(data),
+            _ => None,
+        }
+    }
+}
+
+impl PsfFile {
+    /// Reads and parses the PSF file at `path`.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let data = std::fs::read(path)?;
+        let ast = Ast::parse(&data)?;
+        let header = ast.header();
+        let analysis_type = header
+            .get("analysis type")
+            .ok_or_else(|| anyhow!("missing `analysis type` header value"))?;
+        let analysis = Analysis::from_ast(analysis_type, path, ast)?;
+        Ok(Self { header, analysis })
+    }
+}
@@ -1,7 +1,11 @@
+use crate::analysis::ac::AcData;
+use crate::analysis::dc::DcData;
+use crate::analysis::noise::NoiseData;
 use crate::analysis::transient::TransientData;
 use crate::ascii::parse as ascii_parse;
 use crate::ascii::tests::{SRAM_TINY_PSF, VDIV_SIN_PSF};
 use crate::binary::parse as bin_parse;
+use crate::results::ResultsDir;
 
 use crate::binary::tests::{SRAM_TINY_PSFBIN, VDIV_SIN_PSFBIN};
 
@@ -40,3 +44,162 @@ fn parses_sram_tiny() {
 
     assert!(ascii_data.approx_eq(&bin_data, 1e-12));
 }
+
+static LOG_FILE: &str = r#"HEADER
+"PSFversion" "1.00"
+"simulator" "spectre"
+TYPE
+"analysisInst" STRUCT(
+"analysisType" STRING *
+"dataFile" STRING *
+"format" STRING *
+"parent" STRING *
+"sweepVariable" ARRAY ( * ) STRING *
+"description" STRING *
+) PROP(
+"key" "analysisInst"
+)
+VALUE
+"tran1" "analysisInst" (
+"tran"
+"tran1.tran.tran"
+"PSF"
+""
+( )
+"Transient Analysis `tran1': time = (0 s -> 16 us)"
+) PROP(
+"key" "analysisInst"
+)
+"ac1" "analysisInst" ( "ac" "ac1.ac" "PSF" "" ( "freq" ) "AC Analysis `ac1'" )
+"dcOp" "analysisInst" ( "dc" "dcOp.dc" "PSF" "" ( ) "DC Analysis `dcOp'" )
+"modelParameter" "analysisInst" ( "info" "modelParameter.info" "PSF" "" ( ) "" )
+END
+"#;
+
+#[test]
+fn reads_results_dir() {
+    let dir = tempfile::tempdir().unwrap();
+    let examples = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("examples");
+    std::fs::write(dir.path().join("logFile"), LOG_FILE).unwrap();
+    std::fs::copy(
+        examples.join("vdiv_sin_bin.tran.tran"),
+        dir.path().join("tran1.tran.tran"),
+    )
+    .unwrap();
+    std::fs::copy(
+        examples.join("frequencySweep.ac"),
+        dir.path().join("ac1.ac"),
+    )
+    .unwrap();
+    std::fs::copy(examples.join("dc2.dc"), dir.path().join("dcOp.dc")).unwrap();
+
+    let results = ResultsDir::open(dir.path()).unwrap();
+    assert_eq!(results.analyses().len(), 4);
+    let info = results.info("tran1").unwrap();
+    assert_eq!(info.analysis_type, "tran");
+    assert_eq!(info.data_file, dir.path().join("tran1.tran.tran"));
+    assert_eq!(info.format, "PSF");
+    assert_eq!(info.parent, None);
+    assert_eq!(
+        info.description,
+        "Transient Analysis `tran1': time = (0 s -> 16 us)"
+    );
+    assert_eq!(results.analyses_of_type("ac").count(), 1);
+
+    let tran = results.analysis("tran1").unwrap().tran().unwrap();
+    assert_eq!(tran.signal("time").unwrap().len(), 16001);
+    // Subsequent accesses are served from the cache.
+    assert!(std::ptr::eq(
+        tran,
+        results.analysis("tran1").unwrap().tran().unwrap()
+    ));
+    assert_eq!(
+        results.analysis("ac1").unwrap().ac().unwrap().freq.len(),
+        13
+    );
+    assert!(matches!(
+        results.analysis("dcOp").unwrap().dc().unwrap(),
+        DcData::Op(_)
+    ));
+    let ac: AcData = results.load("ac1").unwrap();
+    assert_eq!(ac.signals.len(), 3);
+    // Loading an analysis as the wrong type fails instead of panicking.
+    assert!(results.load::<AcData>("tran1").is_err());
+    assert!(results.load::<NoiseData>("tran1").is_err());
+    assert!(results.load::<TransientData>("ac1").is_err());
+
+    assert!(results.analysis("modelParameter").is_err());
+    assert!(results.analysis("missing").is_err());
+}
+
+#[test]
+fn opens_psf_files() {
+    use crate::results::{Analysis, PsfFile};
+
+    let examples = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("examples");
+    let file = PsfFile::open(examples.join("vdiv_sin_bin.tran.tran")).unwrap();
+    assert_eq!(file.header["analysis type"], "tran");
+    assert_eq!(file.header["simulator"], "spectre");
+    assert_eq!(
+        file.analysis.tran().unwrap().signal("time").unwrap().len(),
+        16001
+    );
+
+    let file = PsfFile::open(examples.join("frequencySweep.ac")).unwrap();
+    assert_eq!(file.analysis.ac().unwrap().freq.len(), 13);
+    let file = PsfFile::open(examples.join("dc2.dc")).unwrap();
+    assert!(matches!(file.analysis, Analysis::Dc(DcData::Op(_))));
+    assert!(PsfFile::open(examples.join("missing.tran")).is_err());
+}
+
+#[test]
+fn owned_ast_round_trip() {
+    use crate::ascii::owned::PsfAst as OwnedAsciiAst;
+    use crate::binary::owned::PsfAst as OwnedBinaryAst;
+
+    let ast = ascii_parse(VDIV_SIN_PSF).unwrap();
+    let owned = OwnedAsciiAst::from(&ast);
+    assert_eq!(crate::ascii::ast::PsfAst::from(&owned), ast);
+
+    let ast = bin_parse(VDIV_SIN_PSFBIN).unwrap();
+    let owned = OwnedBinaryAst::from(&ast);
+    let borrowed = crate::binary::ast::PsfAst::from(&owned);
+    assert_eq!(borrowed, ast);
+    assert_eq!(
+        TransientData::from_binary(borrowed),
+        TransientData::from_binary(ast)
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_round_trip() {
+    use crate::ascii::owned::PsfAst as OwnedAsciiAst;
+    use crate::ascii::tests::AC_EXAMPLE_PSF;
+    use crate::binary::owned::PsfAst as OwnedBinaryAst;
+
+    let ast = ascii_parse(VDIV_SIN_PSF).unwrap();
+    let json = serde_json::to_string(&ast).unwrap();
+    let owned: OwnedAsciiAst = serde_json::from_str(&json).unwrap();
+    assert_eq!(owned, OwnedAsciiAst::from(&ast));
+    let data = TransientData::from_ascii(&(&owned).into());
+    assert_eq!(data, TransientData::from_ascii(&ast));
+
+    let json = serde_json::to_string(&data).unwrap();
+    assert_eq!(serde_json::from_str::<TransientData>(&json).unwrap(), data);
+
+    let ast = bin_parse(VDIV_SIN_PSFBIN).unwrap();
+    let json = serde_json::to_string(&ast).unwrap();
+    let owned: OwnedBinaryAst = serde_json::from_str(&json).unwrap();
+    assert_eq!(owned, OwnedBinaryAst::from(&ast));
+
+    let ast = ascii_parse(AC_EXAMPLE_PSF).unwrap();
+    let data = AcData::from_ascii(&ast);
+    let json = serde_json::to_string(&data).unwrap();
+    assert_eq!(serde_json::from_str::<AcData>(&json).unwrap(), data);
+
+    let ast = ascii_parse(crate::ascii::tests::DC_EXAMPLE1_PSF).unwrap();
+    let data = DcData::from_ast(&ast);
+    let json = serde_json::to_string(&data).unwrap();
+    assert_eq!(serde_json::from_str::<DcData>(&json).unwrap(), data);
+}
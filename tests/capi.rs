@@ -0,0 +1,55 @@
+//! Compiles and runs a C program against the shared library built with the `capi` feature.
+#![cfg(all(feature = "capi", unix))]
+
+use std::path::Path;
+use std::process::Command;
+
+#[test]
+fn c_program() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    // Build the shared library in a separate target directory,
+    // since the one running this test is locked by cargo.
+    let target_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("capi");
+    let status = Command::new(env!("CARGO"))
+        .args([
+            "rustc",
+            "--lib",
+            "--crate-type",
+            "cdylib",
+            "--features",
+            "capi",
+        ])
+        .arg("--manifest-path")
+        .arg(root.join("Cargo.toml"))
+        .arg("--target-dir")
+        .arg(&target_dir)
+        .status()
+        .expect("failed to run cargo");
+    assert!(status.success(), "failed to build the shared library");
+    let lib_dir = target_dir.join("debug");
+    let exe = Path::new(env!("CARGO_TARGET_TMPDIR")).join("capi_test");
+
+    let cc = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let status = Command::new(cc)
+        .args(["-std=c99", "-Wall", "-Werror", "-o"])
+        .arg(&exe)
+        .arg(root.join("tests/capi/test.c"))
+        .arg("-I")
+        .arg(root.join("include"))
+        .arg("-L")
+        .arg(&lib_dir)
+        .arg(format!("-Wl,-rpath,{}", lib_dir.display()))
+        .arg("-lpsfparser")
+        .status()
+        .expect("failed to run the C compiler");
+    assert!(status.success(), "failed to compile tests/capi/test.c");
+
+    // cargo adds its own target directory to the library search path,
+    // which takes precedence over the rpath and may hold a stale library.
+    let status = Command::new(&exe)
+        .env("LD_LIBRARY_PATH", &lib_dir)
+        .arg(root.join("examples/vdiv_sin_bin.tran.tran"))
+        .status()
+        .unwrap();
+    assert!(status.success(), "C test program failed");
+}
@@ -0,0 +1,59 @@
+/* Exercises the C API on a transient analysis.
+ *
+ * Usage: test <path to vdiv_sin_bin.tran.tran>
+ */
+
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "psfparser.h"
+
+#define CHECK(cond)                                                         \
+  do {                                                                      \
+    if (!(cond)) {                                                          \
+      fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, #cond); \
+      return 1;                                                             \
+    }                                                                       \
+  } while (0)
+
+int main(int argc, char **argv) {
+  CHECK(argc == 2);
+
+  CHECK(psf_open("missing.tran") == NULL);
+  CHECK(psf_last_error() != NULL);
+
+  PsfHandle *psf = psf_open(argv[1]);
+  if (psf == NULL) {
+    fprintf(stderr, "failed to open %s: %s\n", argv[1], psf_last_error());
+    return 1;
+  }
+
+  const char *simulator = psf_header_value(psf, "simulator");
+  CHECK(simulator != NULL && strcmp(simulator, "spectre") == 0);
+  CHECK(psf_header_value(psf, "missing") == NULL);
+
+  size_t count = psf_signal_count(psf);
+  CHECK(count > 1);
+  for (size_t i = 0; i < count; i++) {
+    const char *name = psf_signal_name(psf, i);
+    CHECK(name != NULL);
+    CHECK(psf_signal_is_complex(psf, name) == 0);
+    ptrdiff_t len = psf_signal_len(psf, name);
+    CHECK(len == 16001);
+
+    double *values = malloc(len * sizeof(double));
+    CHECK(values != NULL);
+    CHECK(psf_copy_signal(psf, name, values, len) == len);
+    if (i == 0) {
+      CHECK(strcmp(name, "time") == 0);
+      CHECK(values[0] == 0.0 && values[len - 1] > values[0]);
+    }
+    free(values);
+  }
+  CHECK(psf_signal_name(psf, count) == NULL);
+  CHECK(psf_signal_len(psf, "missing") == -1);
+
+  psf_close(psf);
+  return 0;
+}